@@ -1,41 +1,223 @@
 use std::ops::Deref;
+use std::rc::Rc;
+use gloo::file::callbacks::{read_as_bytes, read_as_text, FileReader};
 use web_sys::wasm_bindgen::JsCast;
 use yew::prelude::*;
-use yew_json_viewer::{common::CopyButton, css::JSON_DOCUMENT, JsonViewer, JsonViewerOption};
+use yew_json_viewer::{binary::{self, BinaryFormat}, common::CopyButton, css::JSON_DOCUMENT, document::ValueRef, error::{caret_snippet, offset, ViewerError}, format::{to_yaml, Format}, jq::JqFilter, parse, JsonViewer, JsonViewerOption};
+
+/// A file dropped on the text area or chosen with the file picker. Text is kept out of the text area,
+/// which gets slow with multi-megabyte content.
+#[derive(PartialEq)]
+struct LoadedFile {
+    name: String,
+    size: u64,
+    content: FileContent,
+}
+
+#[derive(PartialEq)]
+enum FileContent {
+    Text(Rc<str>),
+    Binary(Result<ValueRef, ViewerError>),
+}
+
+const ACCEPTED_FILES: &str = ".json,.json5,.ndjson,.jsonl,.yaml,.yml,.toml,.cbor,.msgpack";
+
+fn format_size(size: u64) -> String {
+    let mut size = size as f64;
+    for unit in ["B", "KB", "MB"] {
+        if size < 1024.0 {
+            return if unit == "B" { format!("{} {}", size, unit) } else { format!("{:.1} {}", size, unit) };
+        }
+        size /= 1024.0;
+    }
+    format!("{:.1} GB", size)
+}
+
+/// Selects the character at `line`:`column` of the text area and scrolls it into view, using the
+/// position of the mark in the backdrop so that wrapped lines are accounted for.
+fn move_cursor(textarea: &NodeRef, backdrop: &NodeRef, text: &str, line: usize, column: usize) {
+    let (Some(element), Some(start)) = (textarea.cast::<web_sys::HtmlTextAreaElement>(), offset(text, line, column)) else {
+        return;
+    };
+    let end = start + text[start..].chars().next().filter(|c| *c != '\n').map_or(0, char::len_utf8);
+    // selection offsets are in UTF-16 code units
+    let utf16 = |index: usize| text[..index].encode_utf16().count() as u32;
+    let _ = element.focus();
+    let _ = element.set_selection_range(utf16(start), utf16(end));
+    let mark = backdrop.cast::<web_sys::Element>()
+        .and_then(|backdrop| backdrop.query_selector("mark").ok().flatten())
+        .and_then(|mark| mark.dyn_into::<web_sys::HtmlElement>().ok());
+    if let Some(mark) = mark {
+        element.set_scroll_top(mark.offset_top() + mark.offset_height() / 2 - element.client_height() / 2);
+    }
+}
+
+/// Shared by the text area and its backdrop so that both wrap lines at the same width.
+const SCROLL_STYLE: &str = "overflow-y: scroll; scrollbar-gutter: stable;";
+
+const BACKDROP_STYLE: &str = "position: absolute; top: 0; left: 0; width: 100%; height: 100%; \
+    white-space: pre-wrap; overflow-wrap: break-word; color: transparent;";
 
 #[function_component(App)]
 pub fn app() -> Html {
     let data = use_state(|| "".to_string());
-    let use_json5 = use_state(|| false);
+    let format = use_state(Format::default);
+    let lenient = use_state(|| false);
+    let jq_filter = use_state(|| "".to_string());
+    // shown instead of the textarea content until the next edit
+    let loaded = use_state(|| None::<Rc<LoadedFile>>);
+    let reader = use_mut_ref(|| None::<FileReader>);
+    let textarea = use_node_ref();
+    let backdrop = use_node_ref();
     let oninput = {
         let data = data.clone();
+        let loaded = loaded.clone();
         Callback::from(move |e: InputEvent| {
             let target: Option<web_sys::EventTarget> = e.target();
             let element = target.and_then(|t| t.dyn_into::<web_sys::HtmlTextAreaElement>().ok());
             data.set(element.unwrap().value());
+            loaded.set(None);
         })
     };
+    let load_file = Callback::from({
+        let loaded = loaded.clone();
+        let format = format.clone();
+        move |file: web_sys::File| {
+            let file = gloo::file::File::from(file);
+            let (name, size) = (file.name(), file.size());
+            let loaded = loaded.clone();
+            let file_reader = match BinaryFormat::from_extension(&name) {
+                Some(binary_format) => read_as_bytes(&file, move |bytes| {
+                    let value = bytes.map_err(|e| ViewerError::decode(e.to_string(), None))
+                        .and_then(|bytes| binary::decode(&bytes, binary_format));
+                    loaded.set(Some(Rc::new(LoadedFile { name, size, content: FileContent::Binary(value) })));
+                }),
+                None => {
+                    let format = format.clone();
+                    read_as_text(&file, move |text| {
+                        let content = match text {
+                            Ok(text) => {
+                                format.set(Format::detect(&name, &text));
+                                FileContent::Text(text.into())
+                            }
+                            Err(err) => FileContent::Binary(Err(ViewerError::decode(err.to_string(), None))),
+                        };
+                        loaded.set(Some(Rc::new(LoadedFile { name, size, content })));
+                    })
+                }
+            };
+            *reader.borrow_mut() = Some(file_reader);
+        }
+    });
+    let ondragover = Callback::from(|e: DragEvent| e.prevent_default());
+    let ondrop = Callback::from({
+        let load_file = load_file.clone();
+        move |e: DragEvent| {
+            e.prevent_default();
+            if let Some(file) = e.data_transfer().and_then(|t| t.files()).and_then(|files| files.get(0)) {
+                load_file.emit(file);
+            }
+        }
+    });
+    let onpick = Callback::from(move |e: Event| {
+        let element: web_sys::HtmlInputElement = e.target_unchecked_into();
+        if let Some(file) = element.files().and_then(|files| files.get(0)) {
+            load_file.emit(file);
+        }
+        // picking the same file again should reload it
+        element.set_value("");
+    });
 
-    let use_json5_element = {
+    let use_json5 = *format == Format::Json5;
+    let format_element = {
+        let onchange = Callback::from({
+            let format = format.clone();
+            move |e: Event| {
+                let element: web_sys::HtmlSelectElement = e.target_unchecked_into();
+                if let Ok(value) = element.value().parse::<Format>() {
+                    format.set(value);
+                }
+            }
+        });
+        html! {
+            <select style="margin-left: 0.6em; vertical-align: 0.2em;" {onchange}>
+                {for Format::ALL.into_iter().map(|option| html! {
+                    <option value={option.name()} selected={option == *format}>{option.name().to_uppercase()}</option>
+                })}
+            </select>
+        }
+    };
+    let lenient_element = {
         let onclick = Callback::from({
-            let use_json5 = use_json5.clone();
+            let lenient = lenient.clone();
             move |_| {
-                use_json5.set(!*use_json5);
+                lenient.set(!*lenient);
             }
         });
         html! {
             <span style="margin-left: 0.6em; cursor: pointer;" {onclick}>
-                <input type="checkbox" checked={*use_json5} style="cursor: pointer;"/>
-                <label style="margin-left: 0.2em; cursor: pointer; user-select: none;">{"Use JSON5"}</label>
+                <input type="checkbox" checked={*lenient} style="cursor: pointer;"/>
+                <label style="margin-left: 0.2em; cursor: pointer; user-select: none;">{"Repair malformed input"}</label>
             </span>
         }
     };
     
+    let jq_oninput = {
+        let jq_filter = jq_filter.clone();
+        Callback::from(move |e: InputEvent| {
+            let element: web_sys::HtmlInputElement = e.target_unchecked_into();
+            jq_filter.set(element.value());
+        })
+    };
+
+    let transform_element = |value: &serde_json::Value| {
+        let filter = jq_filter.deref().trim();
+        let result = if filter.is_empty() {
+            None
+        } else {
+            Some(JqFilter::parse(filter).and_then(|f| f.run(value)).map(|mut outputs| {
+                if outputs.len() == 1 {
+                    outputs.pop().unwrap()
+                } else {
+                    serde_json::Value::Array(outputs)
+                }
+            }))
+        };
+        html! {
+            <div style="margin-top: 12px;">
+                <span class="h5">{"Transform"}</span>
+                <input class="form-control" style="margin-top: 5px; font-family: monospace;"
+                       placeholder="jq filter, e.g. .items | map(select(.qty > 5))"
+                       value={jq_filter.deref().clone()} oninput={jq_oninput}/>
+                {match result {
+                    None => html! {},
+                    Some(Ok(output)) => {
+                        let option: JsonViewerOption = Default::default();
+                        *option.use_json5.borrow_mut() = use_json5;
+                        html! {
+                            <div>
+                                {JsonViewer::new_with_option(output, option).render()}
+                            </div>
+                        }
+                    },
+                    Some(Err(err)) => html! {
+                        <pre class={JSON_DOCUMENT.as_str()}>{format!("jq error: {}", err)}</pre>
+                    },
+                }}
+            </div>
+        }
+    };
+
+    let file_info = loaded.as_ref().map(|file| html! {
+        <div class="text-muted">{format!("{} ({})", file.name, format_size(file.size))}</div>
+    });
     let raw_text_element = |text: String| {
         html! {
             <>
+                {file_info.clone()}
                 <span class="h5">{"Parse Result"}</span>
-                {use_json5_element.clone()}
+                {format_element.clone()}
+                {lenient_element.clone()}
                 if !text.is_empty() {
                     <span style="vertical-align: 0.2em; margin-left: 0.2em;">
                         <CopyButton text={text.clone()}/>
@@ -46,43 +228,110 @@ pub fn app() -> Html {
         }
     };
 
-    let element = if data.deref().is_empty() {
-        raw_text_element("".to_string())
-    } else {
-        let de = if *use_json5 {
-            json5::from_str::<serde_json::Value>(data.deref().as_str()).map_err(|e| e.to_string())
-        } else {
-            serde_json::from_str::<serde_json::Value>(data.deref().as_str()).map_err(|e| e.to_string())
-        };
-        match de {
-            Ok(value) => {
-                let option: JsonViewerOption = Default::default();
-                *option.use_json5.borrow_mut() = *use_json5;
-                html! {
-                    <>
-                        <span class="h5">{"Parse Result"}</span>
-                        {use_json5_element}
-                        <span style="vertical-align: 0.2em; margin-left: 0.2em;">
-                            <CopyButton text={serde_json::to_string_pretty(&value).unwrap()}/>
-                        </span>
-                        <div>
-                            {JsonViewer::new_with_option(value, option).render()}
-                        </div>
-                    </>
-                }
-            },
-            Err(err) => {
-                let msg = format!("Parse error: {}", err);
-                raw_text_element(msg)
-            },
+    // only reparse when the input changes, not on every jq keystroke
+    let parsed = use_memo((loaded.deref().clone(), data.deref().clone(), *format, *lenient), |(loaded, data, format, lenient)| {
+        let parse = if *lenient { parse::parse_lenient } else { parse::parse };
+        match loaded.as_ref().map(|file| &file.content) {
+            Some(FileContent::Binary(value)) => Some(value.clone()),
+            Some(FileContent::Text(text)) => Some(parse(text, *format)),
+            None if data.is_empty() => None,
+            None => Some(parse(data, *format)),
         }
+    });
+    let element = match parsed.deref().clone() {
+        None => raw_text_element("".to_string()),
+        Some(Ok(value)) => {
+            let option = JsonViewerOption { toolbar: true, ..Default::default() };
+            *option.use_json5.borrow_mut() = use_json5;
+            let transform = transform_element(value.get());
+            html! {
+                <>
+                    {file_info}
+                    <span class="h5">{"Parse Result"}</span>
+                    {format_element}
+                    {lenient_element}
+                    <span style="vertical-align: 0.2em; margin-left: 0.2em;">
+                        <CopyButton text={serde_json::to_string_pretty(value.get()).unwrap()}/>
+                        <CopyButton text={to_yaml(value.get())} label="YAML"/>
+                    </span>
+                    <div>
+                        {JsonViewer::new_with_option(value, option).render()}
+                    </div>
+                    {transform}
+                </>
+            }
+        },
+        Some(Err(err)) => {
+            let source = match loaded.as_ref().map(|file| &file.content) {
+                Some(FileContent::Text(text)) => text.to_string(),
+                Some(FileContent::Binary(_)) => String::new(),
+                None => data.deref().clone(),
+            };
+            let caret = err.line.zip(err.column).and_then(|(line, column)| caret_snippet(&source, line, column));
+            // only text typed into the text area can be pointed at
+            let onclick = err.line.zip(err.column).filter(|_| loaded.is_none()).map(|(line, column)| {
+                let textarea = textarea.clone();
+                let backdrop = backdrop.clone();
+                Callback::from(move |_: MouseEvent| move_cursor(&textarea, &backdrop, &source, line, column))
+            });
+            let style = if onclick.is_some() { "cursor: pointer;" } else { "" };
+            let msg = format!("Parse error: {}", err);
+            html! {
+                <>
+                    {file_info}
+                    <span class="h5">{"Parse Result"}</span>
+                    {format_element}
+                    {lenient_element}
+                    <span style="vertical-align: 0.2em; margin-left: 0.2em;">
+                        <CopyButton text={msg.clone()}/>
+                    </span>
+                    <pre class={JSON_DOCUMENT.as_str()} {style} {onclick}>
+                        {msg}
+                        if let Some(caret) = caret {
+                            {"\n\n"}{caret}
+                        }
+                    </pre>
+                </>
+            }
+        },
     };
+    let error_offset = match parsed.deref() {
+        Some(Err(ViewerError { line: Some(line), column: Some(column), .. })) if loaded.is_none() => offset(&data, *line, *column),
+        _ => None,
+    };
+    // mirrors the text area behind it to highlight the error location
+    let backdrop_html = error_offset.map(|start| {
+        let text = data.deref();
+        let end = start + text[start..].chars().next().filter(|c| *c != '\n').map_or(0, char::len_utf8);
+        let marked = if end > start { text[start..end].to_string() } else { " ".to_string() };
+        html! {
+            <div class="form-control" ref={backdrop.clone()} aria-hidden="true" style={format!("{} {}", BACKDROP_STYLE, SCROLL_STYLE)}>
+                {text[..start].to_string()}
+                <mark style="padding: 0; color: transparent; background-color: #f5c2c7;">{marked}</mark>
+                {format!("{} ", &text[end..])}
+            </div>
+        }
+    });
+    let onscroll = Callback::from({
+        let backdrop = backdrop.clone();
+        move |e: Event| {
+            let element: web_sys::Element = e.target_unchecked_into();
+            if let Some(backdrop) = backdrop.cast::<web_sys::Element>() {
+                backdrop.set_scroll_top(element.scroll_top());
+            }
+        }
+    });
     html! {
         <>
             <div class="container-fluid" style="margin-top: 10px;">
                 <div class="row">
                     <div class="col-4">
-                        <textarea class="form-control" style="resize: none; margin-top: 5px; width: 100%; height: 92vh;" placeholder="Input your JSON" {oninput}/>
+                        <input type="file" class="form-control form-control-sm" accept={ACCEPTED_FILES} onchange={onpick}/>
+                        <div style="position: relative; margin-top: 5px;">
+                            {backdrop_html}
+                            <textarea class="form-control" style={format!("position: relative; resize: none; width: 100%; height: 88vh; background-color: transparent; {}", SCROLL_STYLE)}
+                                      placeholder="Input your JSON, or drop a file" ref={textarea} {oninput} {onscroll} {ondragover} {ondrop}/>
+                        </div>
                         <div style="margin-top: 12px;">
                             <span style="cursor: pointer" onclick={Callback::from(move |_| {
                                     let url = "https://github.com/conanyu/yew-json-viewer";
@@ -0,0 +1,586 @@
+use std::str::FromStr;
+use serde_json::{Map, Number, Value};
+use crate::document::{PathSegment, SourceInfo, ValueRef};
+use crate::error::ViewerError;
+
+const MAX_DEPTH: usize = 512;
+
+/// Binary encodings that can be decoded into the viewer tree.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BinaryFormat {
+    Cbor,
+    MessagePack,
+}
+
+impl BinaryFormat {
+    pub fn name(&self) -> &'static str {
+        match self {
+            BinaryFormat::Cbor => "cbor",
+            BinaryFormat::MessagePack => "msgpack",
+        }
+    }
+
+    pub fn from_extension(file_name: &str) -> Option<Self> {
+        match file_name.rsplit('.').next()?.to_ascii_lowercase().as_str() {
+            "cbor" => Some(BinaryFormat::Cbor),
+            "msgpack" | "mpk" | "mp" => Some(BinaryFormat::MessagePack),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for BinaryFormat {
+    type Err = ViewerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "cbor" => Ok(BinaryFormat::Cbor),
+            "msgpack" | "messagepack" => Ok(BinaryFormat::MessagePack),
+            _ => Err(ViewerError::decode(format!("unknown binary format: {}", s), None)),
+        }
+    }
+}
+
+/// Decodes a CBOR or MessagePack item. Byte strings become hex strings (their length is kept in
+/// `SourceInfo::bytes`), CBOR tags and MessagePack extension types become annotations, and
+/// non-string map keys are stringified.
+pub fn decode(bytes: &[u8], format: BinaryFormat) -> Result<ValueRef, ViewerError> {
+    let mut decoder = Decoder { bytes, offset: 0, source: SourceInfo::default(), record: true };
+    let value = match format {
+        BinaryFormat::Cbor => decoder.cbor("", 0)?,
+        BinaryFormat::MessagePack => decoder.msgpack("", 0)?,
+    };
+    if decoder.offset < bytes.len() {
+        return Err(decoder.error("trailing bytes after the first item"));
+    }
+    Ok(ValueRef::with_source(value, decoder.source))
+}
+
+struct Decoder<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+    source: SourceInfo,
+    /// Off while decoding map keys, which have no pointer of their own.
+    record: bool,
+}
+
+impl<'a> Decoder<'a> {
+    fn error(&self, message: &str) -> ViewerError {
+        ViewerError::decode(message.to_string(), Some(self.offset))
+    }
+
+    fn take(&mut self, length: usize) -> Result<&'a [u8], ViewerError> {
+        let end = self.offset.checked_add(length).filter(|end| *end <= self.bytes.len())
+            .ok_or_else(|| self.error("unexpected end of input"))?;
+        let bytes = &self.bytes[self.offset..end];
+        self.offset = end;
+        Ok(bytes)
+    }
+
+    fn uint(&mut self, size: usize) -> Result<u64, ViewerError> {
+        Ok(self.take(size)?.iter().fold(0, |n, b| (n << 8) | *b as u64))
+    }
+
+    fn length(&mut self, size: usize) -> Result<usize, ViewerError> {
+        let length = self.uint(size)?;
+        // every item takes at least one byte, so this also bounds array and map lengths
+        usize::try_from(length).ok().filter(|length| *length <= self.bytes.len() - self.offset)
+            .ok_or_else(|| self.error("length exceeds the input"))
+    }
+
+    fn bytes_value(&mut self, bytes: &[u8], pointer: &str) -> Value {
+        if self.record {
+            self.source.bytes.insert(pointer.to_string(), bytes.len());
+        }
+        Value::String(hex(bytes))
+    }
+
+    fn annotate(&mut self, pointer: &str, annotation: String) {
+        if self.record {
+            self.source.annotations.entry(pointer.to_string()).or_default().push(annotation);
+        }
+    }
+
+    fn datetime(&mut self, pointer: &str, text: String) -> Value {
+        if self.record {
+            self.source.datetimes.insert(pointer.to_string());
+        }
+        Value::String(text)
+    }
+
+    fn map_key(&mut self, key: impl FnOnce(&mut Self) -> Result<Value, ViewerError>) -> Result<String, ViewerError> {
+        let record = std::mem::replace(&mut self.record, false);
+        let key = key(self);
+        self.record = record;
+        Ok(match key? {
+            Value::String(s) => s,
+            key => key.to_string(),
+        })
+    }
+
+    fn insert(&mut self, result: &mut Map<String, Value>, pointer: String, key: String, value: Value) {
+        if let Some(shadowed) = result.insert(key, value) {
+            if self.record {
+                self.source.duplicates.push(pointer, shadowed);
+            }
+        }
+    }
+
+    fn cbor_argument(&mut self, info: u8) -> Result<Option<u64>, ViewerError> {
+        match info {
+            0..=23 => Ok(Some(info as u64)),
+            24 => self.uint(1).map(Some),
+            25 => self.uint(2).map(Some),
+            26 => self.uint(4).map(Some),
+            27 => self.uint(8).map(Some),
+            31 => Ok(None),
+            _ => Err(self.error("reserved additional information")),
+        }
+    }
+
+    fn cbor_is_break(&mut self) -> Result<bool, ViewerError> {
+        match self.bytes.get(self.offset) {
+            Some(0xff) => {
+                self.offset += 1;
+                Ok(true)
+            }
+            Some(_) => Ok(false),
+            None => Err(self.error("unexpected end of input")),
+        }
+    }
+
+    /// Whether a container of `length` items (`None` for indefinite length) ends after `count` items.
+    fn cbor_is_end(&mut self, length: Option<u64>, count: usize) -> Result<bool, ViewerError> {
+        match length {
+            Some(length) => Ok(count as u64 >= length),
+            None => self.cbor_is_break(),
+        }
+    }
+
+    fn cbor_bytes(&mut self, major: u8, argument: Option<u64>) -> Result<Vec<u8>, ViewerError> {
+        match argument {
+            Some(length) => {
+                let length = usize::try_from(length).map_err(|_| self.error("length exceeds the input"))?;
+                Ok(self.take(length)?.to_vec())
+            }
+            None => {
+                let mut result = vec![];
+                while !self.cbor_is_break()? {
+                    let initial = self.take(1)?[0];
+                    if initial >> 5 != major {
+                        return Err(self.error("chunk of the wrong type in an indefinite-length string"));
+                    }
+                    let argument = self.cbor_argument(initial & 0x1f)?
+                        .ok_or_else(|| self.error("nested indefinite-length string"))?;
+                    result.extend(self.cbor_bytes(major, Some(argument))?);
+                }
+                Ok(result)
+            }
+        }
+    }
+
+    fn cbor(&mut self, pointer: &str, depth: usize) -> Result<Value, ViewerError> {
+        if depth > MAX_DEPTH {
+            return Err(self.error("nesting too deep"));
+        }
+        let initial = self.take(1)?[0];
+        let (major, info) = (initial >> 5, initial & 0x1f);
+        if major == 7 {
+            return self.cbor_simple(info, pointer);
+        }
+        let argument = self.cbor_argument(info)?;
+        if argument.is_none() && matches!(major, 0 | 1 | 6) {
+            return Err(self.error("indefinite length is not allowed here"));
+        }
+        match major {
+            0 => Ok(Value::Number(argument.unwrap().into())),
+            1 => {
+                let n = argument.unwrap();
+                Ok(Value::Number(match i64::try_from(n) {
+                    Ok(n) => (-1 - n).into(),
+                    Err(_) => Number::from_str(&format!("-{}", n as u128 + 1)).unwrap(),
+                }))
+            }
+            2 => {
+                let bytes = self.cbor_bytes(2, argument)?;
+                Ok(self.bytes_value(&bytes, pointer))
+            }
+            3 => {
+                let bytes = self.cbor_bytes(3, argument)?;
+                String::from_utf8(bytes).map(Value::String).map_err(|_| self.error("invalid UTF-8 in text string"))
+            }
+            4 => {
+                let mut result = vec![];
+                while !self.cbor_is_end(argument, result.len())? {
+                    let child = format!("{}/{}", pointer, PathSegment::Index(result.len()));
+                    result.push(self.cbor(&child, depth + 1)?);
+                }
+                Ok(Value::Array(result))
+            }
+            5 => {
+                let mut result = Map::new();
+                let mut count = 0;
+                while !self.cbor_is_end(argument, count)? {
+                    let key = self.map_key(|decoder| decoder.cbor("", depth + 1))?;
+                    let child = format!("{}/{}", pointer, PathSegment::Key(key.clone()));
+                    let value = self.cbor(&child, depth + 1)?;
+                    self.insert(&mut result, child, key, value);
+                    count += 1;
+                }
+                Ok(Value::Object(result))
+            }
+            _ => {
+                let tag = argument.unwrap();
+                self.annotate(pointer, format!("tag {}{}", tag, cbor_tag_name(tag).map(|name| format!(" ({})", name)).unwrap_or_default()));
+                let wraps_bytes = self.bytes.get(self.offset).is_some_and(|initial| initial >> 5 == 2);
+                let value = self.cbor(pointer, depth + 1)?;
+                Ok(match (tag, value) {
+                    (0, Value::String(s)) => self.datetime(pointer, s),
+                    (1, Value::Number(n)) => match n.as_f64() {
+                        Some(seconds) => {
+                            let text = format_timestamp(seconds.floor() as i64, ((seconds - seconds.floor()) * 1e9) as u32);
+                            self.datetime(pointer, text)
+                        }
+                        None => Value::Number(n),
+                    },
+                    (2 | 3, Value::String(hex_text)) if wraps_bytes => {
+                        if self.record {
+                            self.source.bytes.remove(pointer);
+                        }
+                        let magnitude = unhex(&hex_text);
+                        let digits = if tag == 2 { decimal(&magnitude) } else { decimal_plus_one(&magnitude) };
+                        let text = if tag == 2 { digits } else { format!("-{}", digits) };
+                        Value::Number(Number::from_str(&text).unwrap())
+                    }
+                    (_, value) => value,
+                })
+            }
+        }
+    }
+
+    fn cbor_simple(&mut self, info: u8, pointer: &str) -> Result<Value, ViewerError> {
+        match info {
+            20 => Ok(Value::Bool(false)),
+            21 => Ok(Value::Bool(true)),
+            22 => Ok(Value::Null),
+            23 => {
+                self.annotate(pointer, "undefined".to_string());
+                Ok(Value::Null)
+            }
+            24 => {
+                let simple = self.uint(1)?;
+                Ok(Value::String(format!("simple({})", simple)))
+            }
+            25 => {
+                let bits = self.uint(2)? as u16;
+                Ok(float_value(half_to_f64(bits)))
+            }
+            26 => {
+                let bits = self.uint(4)? as u32;
+                Ok(float_value(f32::from_bits(bits) as f64))
+            }
+            27 => {
+                let bits = self.uint(8)?;
+                Ok(float_value(f64::from_bits(bits)))
+            }
+            31 => Err(self.error("unexpected break")),
+            28..=30 => Err(self.error("reserved additional information")),
+            _ => Ok(Value::String(format!("simple({})", info))),
+        }
+    }
+
+    fn msgpack(&mut self, pointer: &str, depth: usize) -> Result<Value, ViewerError> {
+        if depth > MAX_DEPTH {
+            return Err(self.error("nesting too deep"));
+        }
+        let marker = self.take(1)?[0];
+        match marker {
+            0x00..=0x7f => Ok(Value::Number(marker.into())),
+            0x80..=0x8f => self.msgpack_map((marker & 0x0f) as usize, pointer, depth),
+            0x90..=0x9f => self.msgpack_array((marker & 0x0f) as usize, pointer, depth),
+            0xa0..=0xbf => self.msgpack_str((marker & 0x1f) as usize),
+            0xc0 => Ok(Value::Null),
+            0xc2 => Ok(Value::Bool(false)),
+            0xc3 => Ok(Value::Bool(true)),
+            0xc4..=0xc6 => {
+                let length = self.length(1 << (marker - 0xc4))?;
+                let bytes = self.take(length)?;
+                Ok(self.bytes_value(bytes, pointer))
+            }
+            0xc7..=0xc9 => {
+                let length = self.length(1 << (marker - 0xc7))?;
+                self.msgpack_ext(length, pointer)
+            }
+            0xca => {
+                let bits = self.uint(4)? as u32;
+                Ok(float_value(f32::from_bits(bits) as f64))
+            }
+            0xcb => {
+                let bits = self.uint(8)?;
+                Ok(float_value(f64::from_bits(bits)))
+            }
+            0xcc..=0xcf => Ok(Value::Number(self.uint(1 << (marker - 0xcc))?.into())),
+            0xd0..=0xd3 => {
+                let size = 1 << (marker - 0xd0);
+                let n = self.uint(size)?;
+                let shift = 64 - size as u32 * 8;
+                Ok(Value::Number((((n << shift) as i64) >> shift).into()))
+            }
+            0xd4..=0xd8 => self.msgpack_ext(1 << (marker - 0xd4), pointer),
+            0xd9..=0xdb => {
+                let length = self.length(1 << (marker - 0xd9))?;
+                self.msgpack_str(length)
+            }
+            0xdc | 0xdd => {
+                let length = self.length(if marker == 0xdc { 2 } else { 4 })?;
+                self.msgpack_array(length, pointer, depth)
+            }
+            0xde | 0xdf => {
+                let length = self.length(if marker == 0xde { 2 } else { 4 })?;
+                self.msgpack_map(length, pointer, depth)
+            }
+            0xe0..=0xff => Ok(Value::Number((marker as i8 as i64).into())),
+            0xc1 => Err(self.error("invalid marker 0xc1")),
+        }
+    }
+
+    fn msgpack_str(&mut self, length: usize) -> Result<Value, ViewerError> {
+        let bytes = self.take(length)?;
+        std::str::from_utf8(bytes).map(|s| Value::String(s.to_string())).map_err(|_| self.error("invalid UTF-8 in string"))
+    }
+
+    fn msgpack_array(&mut self, length: usize, pointer: &str, depth: usize) -> Result<Value, ViewerError> {
+        let mut result = vec![];
+        for index in 0..length {
+            let child = format!("{}/{}", pointer, PathSegment::Index(index));
+            result.push(self.msgpack(&child, depth + 1)?);
+        }
+        Ok(Value::Array(result))
+    }
+
+    fn msgpack_map(&mut self, length: usize, pointer: &str, depth: usize) -> Result<Value, ViewerError> {
+        let mut result = Map::new();
+        for _ in 0..length {
+            let key = self.map_key(|decoder| decoder.msgpack("", depth + 1))?;
+            let child = format!("{}/{}", pointer, PathSegment::Key(key.clone()));
+            let value = self.msgpack(&child, depth + 1)?;
+            self.insert(&mut result, child, key, value);
+        }
+        Ok(Value::Object(result))
+    }
+
+    fn msgpack_ext(&mut self, length: usize, pointer: &str) -> Result<Value, ViewerError> {
+        let kind = self.take(1)?[0] as i8;
+        let data = self.take(length)?;
+        if kind == -1 {
+            let timestamp = match data.len() {
+                4 => Some((u32::from_be_bytes(data.try_into().unwrap()) as i64, 0)),
+                8 => {
+                    let n = u64::from_be_bytes(data.try_into().unwrap());
+                    Some(((n & 0x3_ffff_ffff) as i64, (n >> 34) as u32))
+                }
+                12 => Some((
+                    i64::from_be_bytes(data[4..].try_into().unwrap()),
+                    u32::from_be_bytes(data[..4].try_into().unwrap()),
+                )),
+                _ => None,
+            };
+            if let Some((seconds, nanos)) = timestamp {
+                self.annotate(pointer, "ext -1 (timestamp)".to_string());
+                return Ok(self.datetime(pointer, format_timestamp(seconds, nanos)));
+            }
+        }
+        self.annotate(pointer, format!("ext {}", kind));
+        Ok(self.bytes_value(data, pointer))
+    }
+}
+
+fn cbor_tag_name(tag: u64) -> Option<&'static str> {
+    Some(match tag {
+        0 => "datetime",
+        1 => "epoch datetime",
+        2 => "unsigned bignum",
+        3 => "negative bignum",
+        4 => "decimal fraction",
+        5 => "bigfloat",
+        21 => "expected base64url",
+        22 => "expected base64",
+        23 => "expected hex",
+        24 => "embedded CBOR",
+        32 => "URI",
+        33 => "base64url",
+        34 => "base64",
+        36 => "MIME message",
+        37 => "UUID",
+        55799 => "self-described CBOR",
+        _ => return None,
+    })
+}
+
+fn float_value(n: f64) -> Value {
+    Number::from_f64(n).map(Value::Number).unwrap_or_else(|| Value::String(n.to_string()))
+}
+
+fn half_to_f64(bits: u16) -> f64 {
+    let exponent = (bits >> 10) & 0x1f;
+    let mantissa = (bits & 0x3ff) as f64;
+    let magnitude = match exponent {
+        0 => mantissa * 2f64.powi(-24),
+        31 if mantissa == 0.0 => f64::INFINITY,
+        31 => f64::NAN,
+        _ => (mantissa + 1024.0) * 2f64.powi(exponent as i32 - 25),
+    };
+    if bits & 0x8000 != 0 { -magnitude } else { magnitude }
+}
+
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+pub fn unhex(text: &str) -> Vec<u8> {
+    (0..text.len() / 2).filter_map(|i| u8::from_str_radix(&text[i * 2..i * 2 + 2], 16).ok()).collect()
+}
+
+pub fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut result = String::new();
+    for chunk in bytes.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (i, b)| n | (*b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                result.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                result.push('=');
+            }
+        }
+    }
+    result
+}
+
+/// Decimal digits of a big-endian unsigned integer.
+fn decimal(magnitude: &[u8]) -> String {
+    let mut digits = vec![];
+    let mut number = magnitude.to_vec();
+    while number.iter().any(|b| *b != 0) {
+        let mut remainder = 0u32;
+        for byte in number.iter_mut() {
+            let current = (remainder << 8) | *byte as u32;
+            *byte = (current / 10) as u8;
+            remainder = current % 10;
+        }
+        digits.push(char::from(b'0' + remainder as u8));
+    }
+    if digits.is_empty() {
+        return "0".to_string();
+    }
+    digits.iter().rev().collect()
+}
+
+fn decimal_plus_one(magnitude: &[u8]) -> String {
+    let mut number = magnitude.to_vec();
+    let mut carry = true;
+    for byte in number.iter_mut().rev() {
+        if !carry {
+            break;
+        }
+        let (sum, overflow) = byte.overflowing_add(1);
+        *byte = sum;
+        carry = overflow;
+    }
+    if carry {
+        number.insert(0, 1);
+    }
+    decimal(&number)
+}
+
+/// RFC 3339 text of a Unix timestamp in UTC.
+fn format_timestamp(seconds: i64, nanos: u32) -> String {
+    let days = seconds.div_euclid(86400);
+    let time = seconds.rem_euclid(86400);
+    // days to civil date, http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    let fraction = if nanos == 0 {
+        String::new()
+    } else {
+        format!(".{:09}", nanos).trim_end_matches('0').to_string()
+    };
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}{}Z",
+        year, month, day, time / 3600, time % 3600 / 60, time % 60, fraction,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use super::*;
+
+    fn cbor(bytes: &[u8]) -> ValueRef {
+        decode(bytes, BinaryFormat::Cbor).unwrap()
+    }
+
+    fn msgpack(bytes: &[u8]) -> ValueRef {
+        decode(bytes, BinaryFormat::MessagePack).unwrap()
+    }
+
+    #[test]
+    fn cbor_values() {
+        assert_eq!(*cbor(&[0xa1, 0x61, b'a', 0x82, 0x01, 0x20]).get(), json!({"a": [1, -1]}));
+        let bytes = cbor(&[0x42, 0x01, 0x02]);
+        assert_eq!(*bytes.get(), json!("0102"));
+        assert_eq!(bytes.byte_length(), Some(2));
+        let mut bignum = vec![0xc2, 0x49, 0x01];
+        bignum.extend([0; 8]);
+        let bignum = cbor(&bignum);
+        assert_eq!(bignum.get().to_string(), "18446744073709551616");
+        assert_eq!(bignum.byte_length(), None);
+        let mut datetime = vec![0xc0, 0x74];
+        datetime.extend(b"2013-03-21T20:04:00Z");
+        let datetime = cbor(&datetime);
+        assert!(datetime.is_datetime());
+        assert_eq!(datetime.annotations(), ["tag 0 (datetime)"]);
+    }
+
+    #[test]
+    fn cbor_duplicates_and_map_keys() {
+        let duplicated = cbor(&[0xa2, 0x61, b'a', 0x01, 0x61, b'a', 0x02]);
+        assert_eq!(*duplicated.get(), json!({"a": 2}));
+        assert_eq!(duplicated.duplicates().get("/a"), Some(&[json!(1)][..]));
+        // {{{0: 0}: h'01'}: "v"}: nothing inside the key is recorded
+        let nested = cbor(&[0xa1, 0xa1, 0xa1, 0x00, 0x00, 0x41, 0x01, 0x61, b'v']);
+        assert_eq!(*nested.get(), json!({r#"{"{\"0\":0}":"01"}"#: "v"}));
+        assert!(nested.source().bytes.is_empty());
+        // {{0: 1, 0: 2}: "v"}
+        let duplicated_key = cbor(&[0xa1, 0xa2, 0x00, 0x01, 0x00, 0x02, 0x61, b'v']);
+        assert_eq!(*duplicated_key.get(), json!({r#"{"0":2}"#: "v"}));
+        assert!(duplicated_key.duplicates().is_empty());
+    }
+
+    #[test]
+    fn msgpack_values() {
+        assert_eq!(*msgpack(&[0x81, 0xa1, b'a', 0x93, 0x01, 0xff, 0xc3]).get(), json!({"a": [1, -1, true]}));
+        let bytes = msgpack(&[0xc4, 0x02, 0x01, 0x02]);
+        assert_eq!(*bytes.get(), json!("0102"));
+        assert_eq!(bytes.byte_length(), Some(2));
+        let timestamp = msgpack(&[0xd6, 0xff, 0x00, 0x00, 0x00, 0x00]);
+        assert_eq!(*timestamp.get(), json!("1970-01-01T00:00:00Z"));
+        assert!(timestamp.is_datetime());
+        assert_eq!(timestamp.annotations(), ["ext -1 (timestamp)"]);
+    }
+
+    #[test]
+    fn malformed_input() {
+        assert!(decode(&[0x01, 0x01], BinaryFormat::Cbor).is_err());
+        assert!(decode(&[0x62, b'a'], BinaryFormat::Cbor).is_err());
+        assert!(decode(&[0xc1], BinaryFormat::MessagePack).is_err());
+        assert!(decode(&[0xdd, 0xff, 0xff, 0xff, 0xff], BinaryFormat::MessagePack).is_err());
+    }
+}
@@ -0,0 +1,101 @@
+use std::collections::BTreeMap;
+use std::rc::Rc;
+use serde::{Deserialize, Serialize};
+use yew::prelude::*;
+use crate::error::ViewerError;
+
+/// Collapse state of the nodes the user (or an API) touched, keyed by JSON Pointer.
+/// Nodes without an entry fall back to `JsonViewerOption::is_default_collapsed`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct CollapseStore {
+    paths: BTreeMap<String, bool>,
+}
+
+impl CollapseStore {
+    pub fn get(&self, pointer: &str) -> Option<bool> {
+        self.paths.get(pointer).copied()
+    }
+
+    pub fn is_collapsed(&self, pointer: &str, default: bool) -> bool {
+        self.get(pointer).unwrap_or(default)
+    }
+
+    pub fn set(&mut self, pointer: String, collapsed: bool) {
+        self.paths.insert(pointer, collapsed);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+
+    pub fn paths(&self) -> impl Iterator<Item = (&str, bool)> {
+        self.paths.iter().map(|(pointer, collapsed)| (pointer.as_str(), *collapsed))
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+
+    pub fn from_json(text: &str) -> Result<Self, ViewerError> {
+        serde_json::from_str(text).map_err(|e| ViewerError::from_serde_json(e, text))
+    }
+}
+
+pub enum CollapseAction {
+    Set(String, bool),
+    /// Opens every listed node, e.g. the ancestors of search or JSONPath matches.
+    Expand(Vec<String>),
+    Reset,
+    Replace(CollapseStore),
+}
+
+impl Reducible for CollapseStore {
+    type Action = CollapseAction;
+
+    fn reduce(self: Rc<Self>, action: Self::Action) -> Rc<Self> {
+        match action {
+            CollapseAction::Set(pointer, collapsed) => {
+                if self.get(&pointer) == Some(collapsed) {
+                    return self;
+                }
+                let mut store = (*self).clone();
+                store.set(pointer, collapsed);
+                Rc::new(store)
+            }
+            CollapseAction::Expand(pointers) => {
+                if pointers.iter().all(|pointer| self.get(pointer) == Some(false)) {
+                    return self;
+                }
+                let mut store = (*self).clone();
+                store.paths.extend(pointers.into_iter().map(|pointer| (pointer, false)));
+                Rc::new(store)
+            }
+            CollapseAction::Reset if self.is_empty() => self,
+            CollapseAction::Reset => Rc::default(),
+            CollapseAction::Replace(store) if store == *self => self,
+            CollapseAction::Replace(store) => Rc::new(store),
+        }
+    }
+}
+
+pub type CollapseContext = UseReducerHandle<CollapseStore>;
+
+pub fn toggle_callback(collapse: CollapseContext, pointer: String, collapsed: bool) -> Callback<MouseEvent> {
+    Callback::from(move |e: MouseEvent| {
+        e.prevent_default();
+        collapse.dispatch(CollapseAction::Set(pointer.clone(), !collapsed));
+    })
+}
+
+/// Expands the node at `pointer` and every node above it.
+pub fn reveal_callback(collapse: CollapseContext, pointer: String) -> Callback<MouseEvent> {
+    Callback::from(move |e: MouseEvent| {
+        e.prevent_default();
+        let pointers = pointer.match_indices('/')
+            .map(|(index, _)| pointer[..index].to_string())
+            .chain(std::iter::once(pointer.clone()))
+            .collect();
+        collapse.dispatch(CollapseAction::Expand(pointers));
+    })
+}
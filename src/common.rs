@@ -20,6 +20,8 @@ extern "C" {
 #[derive(Properties, PartialEq)]
 pub struct CopyButtonProps {
     pub text: String,
+    #[prop_or_default]
+    pub label: Option<AttrValue>,
 }
 
 #[function_component(CopyButton)]
@@ -68,6 +70,9 @@ pub fn copy_button(props: &CopyButtonProps) -> Html {
                     <path d="M9.5 1a.5.5 0 0 1 .5.5v1a.5.5 0 0 1-.5.5h-3a.5.5 0 0 1-.5-.5v-1a.5.5 0 0 1 .5-.5h3zm-3-1A1.5 1.5 0 0 0 5 1.5v1A1.5 1.5 0 0 0 6.5 4h3A1.5 1.5 0 0 0 11 2.5v-1A1.5 1.5 0 0 0 9.5 0h-3z"/>
                 </svg>
             }
+            if let Some(label) = &props.label {
+                <span style="margin-left: 2px; font-size: 0.8em; vertical-align: 0.2em;">{label}</span>
+            }
         </span>
     }
 }
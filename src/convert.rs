@@ -0,0 +1,119 @@
+use js_sys::{Array, ArrayBuffer, BigInt, DataView, Date, Function, Map, Object, Reflect, Set, Uint8Array};
+use serde_json::{Map as JsonMap, Number, Value};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use crate::error::ViewerError;
+
+/// Converts an in-memory JavaScript value into a `Value`.
+///
+/// `undefined`, functions, symbols, `NaN`, infinities and `toJSON` follow `JSON.stringify`. Beyond that:
+/// - `BigInt` becomes a number with all of its digits
+/// - `Map` becomes an object keyed by the stringified keys, `Set` becomes an array
+/// - `Date` becomes its ISO 8601 string (`null` for invalid dates)
+/// - typed arrays, `DataView` and `ArrayBuffer` become arrays of numbers
+pub fn js_to_value(value: &JsValue) -> Result<Value, ViewerError> {
+    Ok(convert(value, &mut vec![])?.unwrap_or(Value::Null))
+}
+
+fn convert(value: &JsValue, ancestors: &mut Vec<JsValue>) -> Result<Option<Value>, ViewerError> {
+    if value.is_undefined() || value.is_function() || value.is_symbol() {
+        return Ok(None);
+    }
+    if value.is_null() {
+        return Ok(Some(Value::Null));
+    }
+    if let Some(b) = value.as_bool() {
+        return Ok(Some(Value::Bool(b)));
+    }
+    if let Some(n) = value.as_f64() {
+        return Ok(Some(Number::from_f64(n).map(Value::Number).unwrap_or(Value::Null)));
+    }
+    if let Some(s) = value.as_string() {
+        return Ok(Some(Value::String(s)));
+    }
+    if value.is_bigint() {
+        return Ok(Some(convert_bigint(value.unchecked_ref::<BigInt>())));
+    }
+    if let Some(date) = value.dyn_ref::<Date>() {
+        return Ok(Some(if date.get_time().is_nan() {
+            Value::Null
+        } else {
+            Value::String(String::from(date.to_iso_string()))
+        }));
+    }
+    if ancestors.iter().any(|ancestor| Object::is(ancestor, value)) {
+        return Err(ViewerError::convert("cyclic object value".to_string()));
+    }
+    ancestors.push(value.clone());
+    let result = convert_object(value, ancestors);
+    ancestors.pop();
+    result.map(Some)
+}
+
+fn convert_object(value: &JsValue, ancestors: &mut Vec<JsValue>) -> Result<Value, ViewerError> {
+    if let Ok(to_json) = Reflect::get(value, &JsValue::from_str("toJSON")) {
+        if let Some(to_json) = to_json.dyn_ref::<Function>() {
+            let converted = to_json.call0(value).map_err(|e| ViewerError::convert(js_error_message(&e)))?;
+            return Ok(convert(&converted, ancestors)?.unwrap_or(Value::Null));
+        }
+    }
+    if let Some(buffer) = value.dyn_ref::<ArrayBuffer>() {
+        return Ok(bytes_to_value(Uint8Array::new(buffer)));
+    }
+    if let Some(view) = value.dyn_ref::<DataView>() {
+        let bytes = Uint8Array::new_with_byte_offset_and_length(&view.buffer(), view.byte_offset() as u32, view.byte_length() as u32);
+        return Ok(bytes_to_value(bytes));
+    }
+    if Array::is_array(value) || ArrayBuffer::is_view(value) || value.is_instance_of::<Set>() {
+        let mut result = vec![];
+        for item in Array::from(value).iter() {
+            result.push(convert(&item, ancestors)?.unwrap_or(Value::Null));
+        }
+        return Ok(Value::Array(result));
+    }
+    let mut result = JsonMap::new();
+    if let Some(map) = value.dyn_ref::<Map>() {
+        for entry in map.entries().into_iter() {
+            let entry = Array::from(&entry.map_err(|e| ViewerError::convert(js_error_message(&e)))?);
+            if let Some(item) = convert(&entry.get(1), ancestors)? {
+                result.insert(key_to_string(&entry.get(0)), item);
+            }
+        }
+    } else {
+        for entry in Object::entries(value.unchecked_ref::<Object>()).iter() {
+            let entry = Array::from(&entry);
+            if let Some(item) = convert(&entry.get(1), ancestors)? {
+                result.insert(key_to_string(&entry.get(0)), item);
+            }
+        }
+    }
+    Ok(Value::Object(result))
+}
+
+fn convert_bigint(value: &BigInt) -> Value {
+    let s = String::from(value.to_string(10).unwrap_or_else(|_| "0".into()));
+    serde_json::from_str::<Number>(&s).map(Value::Number).unwrap_or(Value::String(s))
+}
+
+fn bytes_to_value(bytes: Uint8Array) -> Value {
+    Value::Array(bytes.to_vec().into_iter().map(|b| Value::Number(b.into())).collect())
+}
+
+fn key_to_string(key: &JsValue) -> String {
+    if let Some(s) = key.as_string() {
+        s
+    } else if key.is_null() {
+        "null".to_string()
+    } else if key.is_undefined() {
+        "undefined".to_string()
+    } else {
+        String::from(key.unchecked_ref::<Object>().to_string())
+    }
+}
+
+fn js_error_message(err: &JsValue) -> String {
+    err.dyn_ref::<js_sys::Error>()
+        .map(|e| String::from(e.message()))
+        .or_else(|| err.as_string())
+        .unwrap_or_else(|| "failed to convert value".to_string())
+}
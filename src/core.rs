@@ -1,16 +1,28 @@
-use std::ops::Deref;
 use std::rc::Rc;
-use serde_json::Value;
+use serde_json::{Number, Value};
 use yew::prelude::*;
+use crate::collapse::{reveal_callback, toggle_callback, CollapseAction, CollapseContext, CollapseStore};
+use crate::binary::{base64, unhex};
 use crate::common::is_collapsable;
-use crate::common::value_length;
 use crate::interaction::ButtonControlDialogJsonViewer;
 use crate::JsonViewerOption;
 use crate::css::*;
+use crate::diff::Change;
+use crate::document::{PathSegment, ValueRef};
+use crate::edit::{add_button, remove_button, rename_callback, replace_callback, EditContext, EditEvent, Editable};
+use crate::jsonpath::{JsonPathContext, JsonPathMode, JsonPathState};
+use crate::history::{EditCommand, EditHistory};
+use crate::patch::PatchOperation;
+use crate::schema::{self, SchemaContext, Validation};
+use crate::search::{self, Matcher, SearchBar, SearchContext, SearchQuery, SearchState};
+use crate::toolbar::Toolbar;
+use crate::virtual_scroll::VirtualRender;
+use gloo::timers::callback::Timeout;
+use web_sys::{Element, HtmlInputElement};
 
 #[derive(PartialEq, Properties)]
 pub struct RenderProps {
-    pub value: Rc<Value>,
+    pub value: ValueRef,
     pub option: Rc<JsonViewerOption>,
     #[prop_or_default]
     pub father_collapsed: bool,
@@ -18,24 +30,32 @@ pub struct RenderProps {
     pub onclick: Callback<MouseEvent>,
     #[prop_or_default]
     pub is_root: bool,
+    /// Passed only to the containers on the path to the target, so that the others don't re-render.
+    #[prop_or_default]
+    pub scroll_target: Option<ScrollTarget>,
 }
 
-fn collapsed_callback(collapsed: UseStateHandle<Vec<bool>>, index: usize) -> Callback<MouseEvent> {
-    Callback::from(move |e: MouseEvent| {
-        e.prevent_default();
-        let mut temp = collapsed.deref().clone();
-        if index < temp.len() {
-            *temp.get_mut(index).unwrap() = !temp.get(index).unwrap();
-        }
-        collapsed.set(temp);
-    })
+/// Depth the tree was last expanded to (`None` falls back to the option defaults) and a counter
+/// that bumps whenever the expansion is reset.
+#[derive(Clone, Copy, PartialEq, Default)]
+pub struct ExpandState {
+    pub depth: Option<usize>,
+    pub generation: usize,
 }
 
-fn collapsed_class(collapsed: bool) -> Option<&'static str> {
+/// The node a list above the tree last jumped to, and the number of jumps so that jumping to the
+/// same node again scrolls again. Paged containers on its path show enough items to include it.
+#[derive(Clone, PartialEq, Default)]
+pub struct ScrollTarget {
+    pub pointer: Option<String>,
+    pub jumps: usize,
+}
+
+pub(crate) fn collapsed_class(collapsed: bool) -> Option<&'static str> {
     collapsed.then(|| "collapsed")
 }
 
-fn is_string_json(value: &Value, use_json5: bool) -> Option<Value> {
+pub(crate) fn is_string_json(value: &Value, use_json5: bool) -> Option<Value> {
     if value.is_string() {
         let s = value.as_str().unwrap();
         if let Ok(value) = if use_json5 {
@@ -49,66 +69,765 @@ fn is_string_json(value: &Value, use_json5: bool) -> Option<Value> {
     None
 }
 
-#[function_component(RootRender)]
-pub fn root_render(props: &RenderProps) -> Html {
-    let RenderProps { value, option, .. } = props;
-    let root_collapsable = option.root_collapsable || option.is_force_default_collapse(value_length(value));
-    let collapsed = use_state(|| root_collapsable);
-    let onclick = Callback::from({
-        let collapsed = collapsed.clone();
+pub(crate) fn placeholder_text(total: usize) -> String {
+    format!("{} {}", total, if total > 1 { "items" } else { "item" })
+}
+
+pub(crate) fn text_html(text: String, highlight: Option<(&Matcher, bool)>) -> Html {
+    match highlight {
+        Some((matcher, current)) => search::highlight(text.as_str(), matcher, current),
+        None => html! { <>{text}</> },
+    }
+}
+
+/// `text` as a JSON string literal. Matches are found in the raw text, as when counting them.
+pub(crate) fn quoted_html(text: &str, highlight: Option<(&Matcher, bool)>) -> Html {
+    match highlight {
+        Some((matcher, current)) => search::highlight_quoted(text, matcher, current),
+        None => html! { <>{serde_json::to_string(text).unwrap()}</> },
+    }
+}
+
+pub(crate) fn scalar_html(value: &Value, highlight: Option<(&Matcher, bool)>) -> Html {
+    match value {
+        Value::String(s) => {
+            let s = quoted_html(s, highlight);
+            html! {
+                <span class={classes!(JSON_STRING.as_str())}>{s}</span>
+            }
+        }
+        Value::Number(n) => {
+            let unsafe_integer = is_unsafe_integer(n).then(|| html! {
+                <span class={classes!(JSON_BADGE.as_str())}
+                      title="Integer outside the JavaScript safe range (±2^53 - 1), JSON.parse would round it">
+                    {"unsafe integer"}
+                </span>
+            });
+            let n = text_html(n.to_string(), highlight);
+            html! { <><span class={classes!(JSON_LITERAL.as_str())}>{n}</span>{unsafe_integer}</> }
+        }
+        Value::Bool(b) => {
+            let b = text_html(b.to_string(), highlight);
+            html! { <span class={classes!(JSON_LITERAL.as_str())}>{b}</span> }
+        }
+        Value::Null => {
+            let null = text_html("null".to_string(), highlight);
+            html! { <span class={classes!(JSON_LITERAL.as_str())}>{null}</span> }
+        }
+        Value::Array(_) => html! { <>{"[]"}</> },
+        Value::Object(_) => html! { <>{"{}"}</> },
+    }
+}
+
+/// Like `scalar_html`, but renders strings the source marked as datetimes as bare typed literals.
+pub(crate) fn value_html(value_ref: &ValueRef, value: &Value, highlight: Option<(&Matcher, bool)>) -> Html {
+    if let Some(error) = value_ref.error() {
+        return html! {
+            <>
+                <span class={classes!(JSON_INVALID.as_str())}>{text_html(value.as_str().unwrap_or_default().to_string(), highlight)}</span>
+                <span class={classes!(JSON_BADGE.as_str())} title={error.to_string()}>{"parse error"}</span>
+            </>
+        };
+    }
+    match value {
+        Value::String(s) if value_ref.byte_length().is_some() => {
+            let length = value_ref.byte_length().unwrap_or_default();
+            let title = format!("base64: {}", base64(&unhex(s)));
+            html! {
+                <>
+                    <span class={classes!(JSON_LITERAL.as_str())}>{text_html(s.clone(), highlight)}</span>
+                    <span class={classes!(JSON_BADGE.as_str())} {title}>
+                        {format!("{} byte{}", format_count(length), if length == 1 { "" } else { "s" })}
+                    </span>
+                </>
+            }
+        }
+        Value::String(s) if value_ref.is_datetime() => html! {
+            <span class={classes!(JSON_DATETIME.as_str())} title="datetime">{text_html(s.clone(), highlight)}</span>
+        },
+        value => scalar_html(value, highlight),
+    }
+}
+
+/// CBOR tags and MessagePack extension types, shown in front of the value they wrap.
+pub(crate) fn annotations_html(value_ref: &ValueRef) -> Html {
+    value_ref.annotations().iter().map(|annotation| html! {
+        <span class={classes!(JSON_ANNOTATION.as_str())}>{annotation}</span>
+    }).collect::<Html>()
+}
+
+pub(crate) fn repairs_html(value_ref: &ValueRef) -> Option<Html> {
+    let repairs = value_ref.repairs();
+    let title = repairs.iter()
+        .map(|repair| format!("line {}, column {}: {}", repair.line, repair.column, repair.message))
+        .collect::<Vec<_>>()
+        .join("\n");
+    (!repairs.is_empty()).then(|| html! {
+        <span class={classes!(JSON_REPAIR.as_str())} {title}>{"repaired"}</span>
+    })
+}
+
+fn repair_list_html(document: &ValueRef, collapse: &CollapseContext) -> Option<Html> {
+    let repairs = document.all_repairs();
+    if repairs.is_empty() {
+        return None;
+    }
+    let items = repairs.iter().map(|repair| {
+        let onclick = reveal_callback(collapse.clone(), repair.pointer.clone());
+        html! {
+            <li>
+                <a href="" {onclick}>{format!("line {}, column {}", repair.line, repair.column)}</a>
+                {format!(": {}", repair.message)}
+            </li>
+        }
+    }).collect::<Html>();
+    Some(html! {
+        <details class={classes!(JSON_REPAIR_LIST.as_str())}>
+            <summary>{format!("Repaired {} fault{}", repairs.len(), if repairs.len() == 1 { "" } else { "s" })}</summary>
+            <ul>{items}</ul>
+        </details>
+    })
+}
+
+pub(crate) fn change_class(value_ref: &ValueRef) -> Option<&'static str> {
+    value_ref.change().map(|change| match change {
+        Change::Added => JSON_DIFF_ADDED.as_str(),
+        Change::Removed => JSON_DIFF_REMOVED.as_str(),
+        Change::Changed(_) => JSON_DIFF_CHANGED.as_str(),
+        Change::Moved(_) => JSON_DIFF_MOVED.as_str(),
+    })
+}
+
+pub(crate) fn change_title(value_ref: &ValueRef) -> Option<String> {
+    value_ref.change().map(Change::description)
+}
+
+fn change_list_html(document: &ValueRef, collapse: &CollapseContext) -> Option<Html> {
+    let changes = document.all_changes();
+    if changes.is_empty() {
+        return None;
+    }
+    let counts = ["added", "removed", "changed", "moved"].into_iter().filter_map(|name| {
+        let count = changes.values().filter(|change| change.name() == name).count();
+        (count > 0).then(|| format!("{} {}", count, name))
+    }).collect::<Vec<_>>();
+    let items = changes.iter().map(|(pointer, change)| {
+        let onclick = reveal_callback(collapse.clone(), pointer.clone());
+        let label = if pointer.is_empty() { "(root)" } else { pointer.as_str() };
+        html! {
+            <li>
+                <a href="" {onclick}>{label}</a>
+                {format!(": {}", change.description())}
+            </li>
+        }
+    }).collect::<Html>();
+    Some(html! {
+        <details class={classes!(JSON_DIFF_LIST.as_str())}>
+            <summary>{counts.join(", ")}</summary>
+            <ul>{items}</ul>
+        </details>
+    })
+}
+
+pub(crate) fn violation_class(value_ref: &ValueRef, schema: &SchemaContext) -> Option<&'static str> {
+    let validation = schema.validation().filter(|validation| !validation.is_valid())?;
+    validation.is_invalid(&value_ref.path().to_string()).then(|| JSON_SCHEMA_INVALID.as_str())
+}
+
+/// A badge listing the schema violations of the value; `data-pointer` lets the summary list find it.
+pub(crate) fn violations_html(value_ref: &ValueRef, schema: &SchemaContext) -> Option<Html> {
+    let validation = schema.validation().filter(|validation| !validation.is_valid())?;
+    let pointer = value_ref.path().to_string();
+    let violations = validation.at(&pointer);
+    if violations.is_empty() {
+        return None;
+    }
+    let title = violations.iter().map(|violation| violation.message.as_str()).collect::<Vec<_>>().join("\n");
+    Some(html! {
+        <span class={classes!(JSON_SCHEMA_ERROR.as_str())} {title} data-pointer={pointer}>{"invalid"}</span>
+    })
+}
+
+/// Escapes `text` for a double-quoted CSS string.
+fn css_string(text: &str) -> String {
+    text.chars().map(|c| match c {
+        '"' | '\\' => format!("\\{}", c),
+        c if c.is_control() => format!("\\{:x} ", c as u32),
+        c => c.to_string(),
+    }).collect()
+}
+
+fn scroll_to_pointer(container: &NodeRef, pointer: &str) {
+    if let Some(element) = container.cast::<Element>() {
+        if let Ok(Some(element)) = element.query_selector(&format!("[data-pointer=\"{}\"]", css_string(pointer))) {
+            element.scroll_into_view_with_bool(false);
+        }
+    }
+}
+
+fn violation_list_html(
+    validation: &Validation,
+    collapse: &CollapseContext,
+    scroll_target: &UseStateHandle<ScrollTarget>,
+    container: &NodeRef,
+) -> Option<Html> {
+    let violations = validation.violations();
+    if violations.is_empty() {
+        return None;
+    }
+    let items = violations.iter().map(|violation| {
+        let reveal = reveal_callback(collapse.clone(), violation.pointer.clone());
+        let onclick = Callback::from({
+            let container = container.clone();
+            let scroll_target = scroll_target.clone();
+            let pointer = violation.pointer.clone();
+            move |e: MouseEvent| {
+                reveal.emit(e);
+                scroll_target.set(ScrollTarget { pointer: Some(pointer.clone()), jumps: scroll_target.jumps + 1 });
+                let container = container.clone();
+                let pointer = pointer.clone();
+                // after the expanded nodes are rendered
+                Timeout::new(0, move || scroll_to_pointer(&container, &pointer)).forget();
+            }
+        });
+        let label = if violation.pointer.is_empty() { "(root)" } else { violation.pointer.as_str() };
+        html! {
+            <li>
+                <a href="" {onclick} title={violation.schema_pointer.clone()}>{label}</a>
+                {format!(": {}", violation.message)}
+            </li>
+        }
+    }).collect::<Html>();
+    Some(html! {
+        <details class={classes!(JSON_SCHEMA_LIST.as_str())}>
+            <summary>{format!("{} schema violation{}", violations.len(), if violations.len() == 1 { "" } else { "s" })}</summary>
+            <ul>{items}</ul>
+        </details>
+    })
+}
+
+pub(crate) fn line_number_html(value_ref: &ValueRef) -> Option<Html> {
+    value_ref.line().map(|line| html! {
+        <span class={classes!(JSON_LINE_NUMBER.as_str())}>{line}</span>
+    })
+}
+
+/// Whether `n` is an integer that a JavaScript `number` can't hold exactly.
+pub(crate) fn is_unsafe_integer(n: &Number) -> bool {
+    let text = n.to_string();
+    let digits = text.trim_start_matches('-');
+    if digits.contains(['.', 'e', 'E']) {
+        return false;
+    }
+    let digits = digits.trim_start_matches('0');
+    const MAX_SAFE_INTEGER: &str = "9007199254740991";
+    digits.len() > MAX_SAFE_INTEGER.len() || (digits.len() == MAX_SAFE_INTEGER.len() && digits > MAX_SAFE_INTEGER)
+}
+
+pub(crate) fn duplicate_badge(shadowed: &[Value]) -> Html {
+    let title = format!(
+        "Duplicate key, the earlier value{} {} ignored: {}",
+        if shadowed.len() > 1 { "s" } else { "" },
+        if shadowed.len() > 1 { "are" } else { "is" },
+        shadowed.iter().map(|value| value.to_string()).collect::<Vec<_>>().join(", "),
+    );
+    html! {
+        <span class={classes!(JSON_BADGE.as_str())} {title}>{"duplicate key"}</span>
+    }
+}
+
+fn shadowed_html(key: &str, shadowed: &[Value]) -> Html {
+    shadowed.iter().map(|value| html! {
+        <div class={classes!(JSON_SHADOWED.as_str())}>
+            {serde_json::to_string(key).unwrap()}{": "}{value.to_string()}{","}
+        </div>
+    }).collect::<Html>()
+}
+
+fn format_count(count: usize) -> String {
+    let digits = count.to_string();
+    let mut result = String::new();
+    for (index, c) in digits.chars().enumerate() {
+        if index > 0 && (digits.len() - index) % 3 == 0 {
+            result.push(',');
+        }
+        result.push(c);
+    }
+    result
+}
+
+fn show_more_html(shown: UseStateHandle<usize>, page_size: usize, length: usize) -> Html {
+    let remaining = length - *shown;
+    let show_next = Callback::from({
+        let shown = shown.clone();
         move |e: MouseEvent| {
             e.prevent_default();
-            collapsed.set(!*collapsed);
+            shown.set(*shown + page_size);
+        }
+    });
+    let show_all = Callback::from(move |e: MouseEvent| {
+        e.prevent_default();
+        shown.set(length);
+    });
+    html! {
+        <li key="show-more">
+            <a href="" class={classes!(JSON_PLACEHOLDER.as_str())} onclick={show_next}>
+                {format!("show next {}", format_count(page_size.min(remaining)))}
+            </a>
+            {"/"}
+            <a href="" class={classes!(JSON_PLACEHOLDER.as_str())} onclick={show_all}>
+                {format!("show all ({} remaining)", format_count(remaining))}
+            </a>
+        </li>
+    }
+}
+
+#[function_component(RootRender)]
+pub fn root_render(props: &RenderProps) -> Html {
+    let RenderProps { value: document, option, .. } = props;
+    let own_history = use_memo((), |_| EditHistory::default());
+    let history = option.history.clone().unwrap_or_else(|| (*own_history).clone());
+    use_effect_with((history.clone(), option.history_limit), |(history, limit)| {
+        history.set_limit(*limit);
+        || {}
+    });
+    // (document the edits were made to, edited document); not a state so that commands sent
+    // before the next render build on each other
+    let edited = use_mut_ref(|| None::<(ValueRef, ValueRef)>);
+    let force_update = use_force_update();
+    let edit_error = use_state_eq(|| None::<String>);
+    // a new document that isn't one of our edits fed back replaces the edits and their history
+    use_effect_with(document.clone(), {
+        let edited = edited.clone();
+        let history = history.clone();
+        move |document: &ValueRef| {
+            if edited.borrow().as_ref().is_some_and(|(base, edited)| base != document && edited != document) {
+                *edited.borrow_mut() = None;
+                history.clear();
+            }
+            || {}
+        }
+    });
+    use_effect_with((history.clone(), document.clone(), option.on_edit.clone()), {
+        let edited = edited.clone();
+        let edit_error = edit_error.clone();
+        move |(history, document, on_edit): &(EditHistory, ValueRef, Option<Callback<EditEvent>>)| {
+            let viewer = Callback::from({
+                let history = history.clone();
+                let document = document.clone();
+                let on_edit = on_edit.clone();
+                move |command: EditCommand| {
+                    let (base, current) = edited.borrow().clone().unwrap_or_else(|| (document.clone(), document.clone()));
+                    let mut value = (**current.root()).clone();
+                    let mut source = current.source().clone();
+                    let patch = history.run(&mut value, command, |value, operation| {
+                        source.apply(operation, |pointer| value.pointer(pointer).is_some_and(Value::is_array));
+                    });
+                    let patch = match patch {
+                        Ok(patch) if patch.is_empty() => return,
+                        Ok(patch) => patch,
+                        Err(err) => {
+                            edit_error.set(Some(err.to_string()));
+                            return;
+                        }
+                    };
+                    edit_error.set(None);
+                    let value = ValueRef::with_source(value, source);
+                    *edited.borrow_mut() = Some((base, value.clone()));
+                    force_update.force_update();
+                    if let Some(on_edit) = &on_edit {
+                        on_edit.emit(EditEvent { value, patch });
+                    }
+                }
+            });
+            history.attach(viewer.clone());
+            let history = history.clone();
+            move || history.detach(&viewer)
+        }
+    });
+    let document = match &*edited.borrow() {
+        Some((base, edited)) if base == document || edited == document => edited.clone(),
+        _ => document.clone(),
+    };
+    let document = &document;
+    let jsonpath = use_memo((document.clone(), option.jsonpath.clone()), |(value, expression)| {
+        expression.as_ref()
+            .filter(|expression| !expression.trim().is_empty())
+            .map(|expression| JsonPathState::new(value.get(), expression).map(Rc::new))
+    });
+    let jsonpath_error = jsonpath.as_ref().as_ref().and_then(|result| result.as_ref().err()).map(|err| err.to_string());
+    let jsonpath_context = JsonPathContext(jsonpath.as_ref().as_ref().and_then(|result| result.as_ref().ok()).cloned());
+    let filtered = use_memo((jsonpath_context.clone(), option.jsonpath_mode), |(context, mode)| {
+        context.state()
+            .filter(|_| *mode == JsonPathMode::Filter)
+            .map(|state| ValueRef::new(Value::Array(state.values.clone())))
+    });
+    let value = filtered.as_ref().as_ref().unwrap_or(document);
+    let validation = use_memo((document.clone(), option.schema.clone()), |(document, schema)| {
+        schema.as_ref().map(|schema| schema::validate(schema, document.get()).map(Rc::new))
+    });
+    let schema_error = validation.as_ref().as_ref().and_then(|result| result.as_ref().err()).map(|err| err.to_string());
+    // like edits, violations are located by pointers into the unfiltered document
+    let schema_context = SchemaContext(validation.as_ref().as_ref()
+        .and_then(|result| result.as_ref().ok())
+        .filter(|_| filtered.is_none())
+        .cloned());
+    // a JSONPath filter renders a new array whose pointers don't match the document
+    let edit_context = EditContext((option.editable && filtered.is_none()).then(|| {
+        let history = history.clone();
+        Callback::from(move |patch: Vec<PatchOperation>| history.send(EditCommand::Apply(patch)))
+    }));
+    let onkeydown = option.editable.then(|| Callback::from(move |e: KeyboardEvent| {
+        // inputs keep their own undo
+        if !(e.ctrl_key() || e.meta_key()) || e.target_dyn_into::<HtmlInputElement>().is_some() {
+            return;
+        }
+        match e.key().to_lowercase().as_str() {
+            "z" if e.shift_key() => history.redo(),
+            "z" => history.undo(),
+            "y" => history.redo(),
+            _ => return,
+        }
+        e.prevent_default();
+    }));
+    let tabindex = option.editable.then_some("0");
+    let jsonpath_context = if option.jsonpath_mode == JsonPathMode::Dim { jsonpath_context } else { Default::default() };
+    // (depth, clicks, option generation the depth was picked in)
+    let toolbar_expand = use_state(|| (None::<usize>, 0usize, option.collapse_generation));
+    let expand = ExpandState {
+        depth: if toolbar_expand.2 == option.collapse_generation && toolbar_expand.0.is_some() {
+            toolbar_expand.0
+        } else {
+            option.expand_depth_override
+        },
+        generation: option.collapse_generation + toolbar_expand.1,
+    };
+    let collapse = use_reducer(|| {
+        option.collapse_state.as_deref().cloned()
+            .or_else(|| option.persist.as_ref().and_then(|persist| persist.load()))
+            .unwrap_or_default()
+    });
+    let last_expand = use_mut_ref(|| expand);
+    use_effect_with(expand, {
+        let collapse = collapse.clone();
+        move |expand: &ExpandState| {
+            if *last_expand.borrow() != *expand {
+                *last_expand.borrow_mut() = *expand;
+                collapse.dispatch(CollapseAction::Reset);
+            }
+            || {}
+        }
+    });
+    use_effect_with(option.collapse_state.clone().map(|state| Rc::as_ptr(&state)), {
+        let collapse = collapse.clone();
+        let state = option.collapse_state.clone();
+        move |_| {
+            if let Some(state) = state {
+                collapse.dispatch(CollapseAction::Replace((*state).clone()));
+            }
+            || {}
+        }
+    });
+    use_effect_with(collapse.clone(), {
+        let on_collapse_change = option.on_collapse_change.clone();
+        let persist = option.persist.clone();
+        move |collapse: &CollapseContext| {
+            if let Some(persist) = persist {
+                persist.save(collapse);
+            }
+            if let Some(on_collapse_change) = on_collapse_change {
+                on_collapse_change.emit((**collapse).clone());
+            }
+            || {}
+        }
+    });
+    let collapsed = collapse.is_collapsed("", option.is_default_collapsed(value.get(), 0, &expand));
+    let query = use_state(|| option.search.clone().unwrap_or_else(|| SearchQuery::new("")));
+    let search_index = use_state(|| option.search_index);
+    use_effect_with((option.search.clone(), option.search_index), {
+        let query = query.clone();
+        let search_index = search_index.clone();
+        move |(search, index): &(Option<SearchQuery>, usize)| {
+            if let Some(search) = search {
+                query.set(search.clone());
+                search_index.set(*index);
+            } else if !query.pattern.is_empty() {
+                query.set(SearchQuery { pattern: "".to_string(), ..(*query).clone() });
+            }
+            || {}
+        }
+    });
+    let search = use_memo((value.clone(), (*query).clone(), *search_index), |(value, query, index)| {
+        SearchState::new(value.get(), query, *index).map(Rc::new)
+    });
+    let context = SearchContext(search.as_ref().as_ref().ok().filter(|_| !query.pattern.is_empty()).cloned());
+    let container = use_node_ref();
+    let scroll_target = use_state(ScrollTarget::default);
+    use_effect_with(jsonpath_context.clone(), {
+        let collapse = collapse.clone();
+        move |context: &JsonPathContext| {
+            if let Some(state) = context.state() {
+                collapse.dispatch(CollapseAction::Expand(state.ancestors().cloned().collect()));
+            }
+            || {}
+        }
+    });
+    use_effect_with(context.clone(), {
+        let collapse = collapse.clone();
+        let container = container.clone();
+        move |context: &SearchContext| {
+            if let Some(state) = context.state() {
+                collapse.dispatch(CollapseAction::Expand(state.ancestors().cloned().collect()));
+            }
+            if context.state().is_some_and(|state| state.current.is_some()) {
+                Timeout::new(0, move || {
+                    if let Some(element) = container.cast::<Element>() {
+                        if let Ok(Some(element)) = element.query_selector(".yew_json_viewer_current_match") {
+                            element.scroll_into_view_with_bool(false);
+                        }
+                    }
+                }).forget();
+            }
+            || {}
         }
     });
+    let search_bar = option.search_bar.then(|| {
+        let on_query = Callback::from({
+            let query = query.clone();
+            let search_index = search_index.clone();
+            move |value: SearchQuery| {
+                query.set(value);
+                search_index.set(0);
+            }
+        });
+        let count = search.as_ref().as_ref().map(|state| state.matches.len()).unwrap_or(0);
+        let on_step = Callback::from({
+            let search_index = search_index.clone();
+            move |delta: isize| {
+                if count > 0 {
+                    search_index.set((*search_index as isize + delta).rem_euclid(count as isize) as usize);
+                }
+            }
+        });
+        let (current, error) = match search.as_ref() {
+            Ok(state) => (state.current, None),
+            Err(err) => (None, Some(err.to_string())),
+        };
+        html! {
+            <SearchBar query={(*query).clone()} {count} {current} {error} {on_query} {on_step}/>
+        }
+    });
+    let toolbar = option.toolbar.then(|| {
+        let on_expand = Callback::from({
+            let toolbar_expand = toolbar_expand.clone();
+            let generation = option.collapse_generation;
+            move |depth: usize| toolbar_expand.set((Some(depth), toolbar_expand.1 + 1, generation))
+        });
+        html! {
+            <Toolbar {on_expand}/>
+        }
+    });
+    let jsonpath_error = jsonpath_error.map(|err| html! {
+        <span class={classes!(JSON_ERROR.as_str())}>{format!("JSONPath error: {}", err)}</span>
+    });
+    let schema_error = schema_error.map(|err| html! {
+        <span class={classes!(JSON_ERROR.as_str())}>{format!("Schema error: {}", err)}</span>
+    });
+    let edit_error = edit_error.as_ref().map(|err| html! {
+        <span class={classes!(JSON_ERROR.as_str())}>{format!("Edit error: {}", err)}</span>
+    });
+    let repair_list = repair_list_html(document, &collapse);
+    let violation_list = schema_context.validation()
+        .and_then(|validation| violation_list_html(validation, &collapse, &scroll_target, &container));
+    let change_list = change_list_html(document, &collapse);
+    if option.virtual_scroll {
+        return html! {
+            <ContextProvider<CollapseContext> context={collapse}>
+                <ContextProvider<ExpandState> context={expand}>
+                    <ContextProvider<SearchContext> {context}>
+                        <ContextProvider<JsonPathContext> context={jsonpath_context}>
+                            <ContextProvider<EditContext> context={edit_context}>
+                                <ContextProvider<SchemaContext> context={schema_context}>
+                                    <ContextProvider<ScrollTarget> context={(*scroll_target).clone()}>
+                                        {toolbar}
+                                        {search_bar}
+                                        {jsonpath_error}
+                                        {schema_error}
+                                        {edit_error}
+                                        {repair_list}
+                                        {change_list}
+                                        {violation_list}
+                                        <VirtualRender value={value.clone()} option={option}/>
+                                    </ContextProvider<ScrollTarget>>
+                                </ContextProvider<SchemaContext>>
+                            </ContextProvider<EditContext>>
+                        </ContextProvider<JsonPathContext>>
+                    </ContextProvider<SearchContext>>
+                </ContextProvider<ExpandState>>
+            </ContextProvider<CollapseContext>>
+        };
+    }
+    let onclick = toggle_callback(collapse.clone(), String::new(), collapsed);
     let inner_html = html! {
         <>
-            if is_collapsable(value) {
-                <a href="" class={classes!(JSON_TOGGLE.as_str(), collapsed_class(*collapsed))} onclick={onclick.clone()}/>
-            } else if let Some(value) = is_string_json(value, *option.use_json5.borrow()) {
-                <ButtonControlDialogJsonViewer {value} /> 
+            if is_collapsable(value.get()) {
+                <a href="" class={classes!(JSON_TOGGLE.as_str(), collapsed_class(collapsed))} onclick={onclick.clone()}/>
+            } else if let Some(value) = is_string_json(value.get(), *option.use_json5.borrow()) {
+                <ButtonControlDialogJsonViewer value={ValueRef::new(value)} />
             }
-            <Render value={value.clone()} option={option} father_collapsed={*collapsed} {onclick} is_root={true}/>
+            <Render value={value.clone()} option={option} father_collapsed={collapsed} {onclick} is_root={true}
+                    scroll_target={Some((*scroll_target).clone())}/>
         </>
     };
     let style = if option.dialog_index.is_some() { "max-height: 77vh" } else { "" };
     html! {
-        <pre class={classes!(JSON_DOCUMENT.as_str())} {style}>
-            {inner_html}
-        </pre>
+        <ContextProvider<CollapseContext> context={collapse}>
+            <ContextProvider<ExpandState> context={expand}>
+                <ContextProvider<SearchContext> {context}>
+                    <ContextProvider<JsonPathContext> context={jsonpath_context}>
+                        <ContextProvider<EditContext> context={edit_context}>
+                            <ContextProvider<SchemaContext> context={schema_context}>
+                                {toolbar}
+                                {search_bar}
+                                {jsonpath_error}
+                                {schema_error}
+                                {edit_error}
+                                {repair_list}
+                                {change_list}
+                                {violation_list}
+                                <pre class={classes!(JSON_DOCUMENT.as_str())} {style} ref={container} {tabindex} {onkeydown}>
+                                    {inner_html}
+                                </pre>
+                            </ContextProvider<SchemaContext>>
+                        </ContextProvider<EditContext>>
+                    </ContextProvider<JsonPathContext>>
+                </ContextProvider<SearchContext>>
+            </ContextProvider<ExpandState>>
+        </ContextProvider<CollapseContext>>
     }
 }
 
-fn get_collapsed_state(value: Rc<Value>, option: Rc<JsonViewerOption>) -> Vec<bool> {
-    match value.deref() {
-        Value::Array(arr) => arr.iter().map(|value| value).collect::<Vec<_>>(),
-        Value::Object(object) => object.iter().map(|(_, value)| value).collect::<Vec<_>>(),
+fn child_segments(value: &Value) -> Vec<PathSegment> {
+    match value {
+        Value::Array(arr) => (0..arr.len()).map(PathSegment::Index).collect(),
+        Value::Object(object) => object.keys().map(|key| PathSegment::Key(key.clone())).collect(),
         _ => vec![],
-    }.iter().map(|value| option.collapsable || option.is_force_default_collapse(value_length(value))).collect::<Vec<_>>()
+    }
+}
+
+fn get_collapsed_state(value_ref: &ValueRef, value: &Value, option: &JsonViewerOption, expand: &ExpandState, collapse: &CollapseStore) -> Vec<bool> {
+    let depth = value_ref.path().depth() + 1;
+    let pointer = value_ref.path().to_string();
+    let children = match value {
+        Value::Array(arr) => arr.iter().collect::<Vec<_>>(),
+        Value::Object(object) => object.values().collect::<Vec<_>>(),
+        _ => vec![],
+    };
+    child_segments(value).into_iter().zip(children).map(|(segment, child)| {
+        let default = option.is_default_collapsed(child, depth, expand);
+        collapse.is_collapsed(format!("{}/{}", pointer, segment).as_str(), default)
+    }).collect::<Vec<_>>()
 }
 
 #[function_component(Render)]
 fn render(props: &RenderProps) -> Html {
     let reverse_father_collapsed = props.onclick.clone();
-    let RenderProps { value, option, father_collapsed, ..} = props;
-    let length = match value.deref() {
+    let RenderProps { value: value_ref, option, father_collapsed, ..} = props;
+    let value = value_ref.get();
+    // kept across renders so that the children's props compare equal
+    let children = use_memo(value_ref.clone(), |value_ref| {
+        child_segments(value_ref.get()).into_iter().map(|segment| value_ref.child(segment)).collect::<Vec<_>>()
+    });
+    let length = match value {
         Value::Array(arr) => arr.len(),
         Value::Object(object) => object.len(),
         _ => 0,
     };
-    let collapsed = use_state(|| get_collapsed_state(value.clone(), option.clone()));
-    use_effect_with((length).clone(), {
-        let collapsed = collapsed.clone();
-        let value = value.clone();
-        let option = option.clone();
+    let expand = use_context::<ExpandState>().unwrap_or_default();
+    let collapse = use_context::<CollapseContext>().expect("Render is mounted inside RootRender");
+    let collapsed = get_collapsed_state(value_ref, value, option, &expand, &collapse);
+    let child_pointer = |segment: PathSegment| format!("{}/{}", value_ref.path(), segment);
+    let shown = use_state(|| option.page_size);
+    use_effect_with((length, expand), {
+        let shown = shown.clone();
+        let page_size = option.page_size;
         move |_| {
-            collapsed.set(get_collapsed_state(value.clone(), option.clone()));
+            shown.set(page_size);
+            || {}
+        }
+    });
+    // the child on the path to the scroll target; only containers on that path get the target
+    let target_child = props.scroll_target.as_ref().and_then(|target| {
+        let pointer = target.pointer.as_deref()?;
+        let rest = pointer.strip_prefix(value_ref.path().to_string().as_str())?.strip_prefix('/')?;
+        let token = rest.split('/').next()?;
+        children.iter().position(|child| child.path().last().is_some_and(|segment| segment.to_string() == token))
+    });
+    use_effect_with((props.scroll_target.as_ref().map(|target| target.jumps), target_child), {
+        let shown = shown.clone();
+        let page_size = option.page_size;
+        move |(_, target_child): &(Option<usize>, Option<usize>)| {
+            if let Some(index) = target_child.filter(|index| page_size > 0 && *index >= *shown) {
+                shown.set(index + 1);
+            }
+            || {}
+        }
+    });
+    let child_scroll_target = |index: usize| props.scroll_target.clone().filter(|_| target_child == Some(index));
+    let search = use_context::<SearchContext>().unwrap_or_default();
+    let jsonpath = use_context::<JsonPathContext>().unwrap_or_default();
+    let edit = use_context::<EditContext>().unwrap_or_default();
+    let edit = edit.callback();
+    let schema = use_context::<SchemaContext>().unwrap_or_default();
+    use_effect_with((search.clone(), jsonpath.clone()), {
+        let shown = shown.clone();
+        let value_ref = value_ref.clone();
+        let page_size = option.page_size;
+        move |(search, jsonpath): &(SearchContext, JsonPathContext)| {
+            if search.state().is_some() || jsonpath.state().is_some() {
+                let pointer = value_ref.path().to_string();
+                let mut last_hit = None;
+                for (index, segment) in child_segments(value_ref.get()).into_iter().enumerate() {
+                    let child = format!("{}/{}", pointer, segment);
+                    let search_hit = search.state().is_some_and(|state| {
+                        state.is_ancestor(&child) || state.key_hit(&child).is_some() || state.value_hit(&child).is_some()
+                    });
+                    let jsonpath_hit = jsonpath.state().is_some_and(|state| {
+                        state.is_ancestor(&child) || state.is_matched(&child)
+                    });
+                    if search_hit || jsonpath_hit {
+                        last_hit = Some(index);
+                    }
+                }
+                if let Some(index) = last_hit.filter(|index| page_size > 0 && *index >= *shown) {
+                    shown.set(index + 1);
+                }
+            }
             || {}
         }
     });
-    let mut arr = if props.is_root { vec![] } else { (option.value_to_element)(value) };
+    let inside_match = jsonpath.state().is_some_and(|state| {
+        state.covers(value_ref.path()) || (!props.is_root && state.is_dimmed(value_ref.path()))
+    });
+    let dimmed_class = |segment: PathSegment| {
+        let state = jsonpath.state().filter(|_| !inside_match)?;
+        let child = value_ref.path().child(segment);
+        state.is_dimmed(&child).then(|| JSON_DIMMED.as_str())
+    };
+    let pointer = search.state().map(|_| value_ref.path().to_string());
+    let key_highlight = |key: &String| {
+        let state = search.state()?;
+        let current = state.key_hit(&format!("{}/{}", pointer.as_ref()?, PathSegment::Key(key.clone())))?;
+        Some((&state.matcher, current))
+    };
+    let value_highlight = search.state().and_then(|state| {
+        Some((&state.matcher, state.value_hit(pointer.as_ref()?)?))
+    });
+    let visible = if option.page_size > 0 { (*shown).min(length) } else { length };
+    let show_more = (visible < length).then(|| show_more_html(shown.clone(), option.page_size, length));
+    let mut arr = if props.is_root { vec![] } else { (option.value_to_element)(value_ref) };
     if let Some(addtional_value_to_element) = &option.additional_value_to_element {
-        let mut additional_element = (addtional_value_to_element)(value);
+        let mut additional_element = (addtional_value_to_element)(value_ref);
         arr.append(&mut additional_element);
     }
     let arr = arr
@@ -122,55 +841,51 @@ fn render(props: &RenderProps) -> Html {
             }
         })
         .collect::<Vec<_>>();
-    let element = match value.deref() {
-        Value::String(s) => {
-            let s = serde_json::to_string(s).unwrap();
-            html! {
-                <span class={classes!(JSON_STRING.as_str())}>{s}</span>
-            }
-        }
-        Value::Number(n) => {
-            let n = n.to_string();
-            html! { <span class={classes!(JSON_LITERAL.as_str())}>{n}</span> }
-        }
-        Value::Bool(b) => {
-            let b = b.to_string();
-            html! { <span class={classes!(JSON_LITERAL.as_str())}>{b}</span> }
-        }
-        Value::Null => {
-            html! { <span class={classes!(JSON_LITERAL.as_str())}>{"null"}</span> }
-        }
+    let element = match value {
+        Value::String(_) | Value::Number(_) | Value::Bool(_) | Value::Null => match edit {
+            Some(edit) => html! {
+                <Editable text={value.to_string()} on_commit={replace_callback(edit, value_ref)}>
+                    {value_html(value_ref, value, value_highlight)}
+                </Editable>
+            },
+            None => value_html(value_ref, value, value_highlight),
+        },
         Value::Array(arr) => {
             if arr.len() > 0 {
                 let mut result = vec![];
-                for (index, item) in arr.iter().enumerate() {
+                for (index, item) in arr.iter().enumerate().take(visible) {
                     let mut current_html = vec![];
-                    let onclick = collapsed_callback(collapsed.clone(), index);
+                    let onclick = toggle_callback(collapse.clone(), child_pointer(PathSegment::Index(index)), collapsed[index]);
                     if is_collapsable(item) {
                         current_html.push(html! {
                             <a key="collapse" href="" class={
-                                classes!(JSON_TOGGLE.as_str(), 
-                                         collapsed_class(collapsed.deref().get(index).unwrap_or(&option.collapsable).clone()))
+                                classes!(JSON_TOGGLE.as_str(), collapsed_class(collapsed[index]))
                             } onclick={onclick.clone()}/>
                         });
                     }
+                    let child = &children[index];
                     current_html.push(html! {
-                        <Render key="render" value={Rc::new(item.clone())} option={option} 
-                                father_collapsed={collapsed.deref().get(index).unwrap_or(&option.collapsable).clone()}
+                        <Render key="render" value={child.clone()} option={option}
+                                father_collapsed={collapsed[index]}
                                 onclick={onclick.clone()}
+                                scroll_target={child_scroll_target(index)}
                         />
                     });
                     result.push(html! {
-                        <li key={index}>
+                        <li key={index} class={classes!(dimmed_class(PathSegment::Index(index)), change_class(child), violation_class(child, &schema))}
+                            title={change_title(child)}>
+                            {line_number_html(child)}
                             {current_html}
                             if index != arr.len() - 1 {
                                 {","}
                             }
+                            {edit.map(|edit| remove_button(edit, child.path().to_string()))}
                         </li>
                     });
                 }
-                let total = arr.len();
-                let placeholder = format!("{} {}", total, if total > 1 { "items" } else { "item" });
+                result.extend(show_more);
+                result.extend(edit.map(|edit| html! { <li key="add">{add_button(edit, value_ref, value)}</li> }));
+                let placeholder = placeholder_text(arr.len());
                 html! {
                     <>
                         {"["}
@@ -184,51 +899,75 @@ fn render(props: &RenderProps) -> Html {
                 }
             } else {
                 html! {
-                    <>{"[]"}</>
+                    <>{"[]"}{edit.map(|edit| add_button(edit, value_ref, value))}</>
                 }
             }
         }
         Value::Object(object) => {
             let mut key_count = object.len();
             if key_count > 0 {
-                let result = object
+                let mut result = object
                     .iter()
                     .enumerate()
+                    .take(visible)
                     .map(|(index, (key, value))| {
                         let key_repr = html! {
                             <span class={classes!(JSON_STRING.as_str())}>
-                                {serde_json::to_string(key).unwrap()}
+                                {quoted_html(key, key_highlight(key))}
                             </span>
                         };
-                        let onclick = collapsed_callback(collapsed.clone(), index);
-                        let key_repr = if is_collapsable(value) {
+                        let shadowed = value_ref.duplicates().get(child_pointer(PathSegment::Key(key.clone())).as_str());
+                        let onclick = toggle_callback(collapse.clone(), child_pointer(PathSegment::Key(key.clone())), collapsed[index]);
+                        let key_repr = if let Some(edit) = edit {
+                            // an input inside the toggle link would collapse the value on every click
+                            html! {
+                                <>
+                                    if is_collapsable(value) {
+                                        <a href="" class={
+                                            classes!(JSON_TOGGLE.as_str(), collapsed_class(collapsed[index]))
+                                        } onclick={onclick.clone()}/>
+                                    }
+                                    <Editable text={key.clone()} on_commit={rename_callback(edit, value_ref, key)}>
+                                        {key_repr}
+                                    </Editable>
+                                </>
+                            }
+                        } else if is_collapsable(value) {
                             html! {
                                 <a href="" class={
-                                    classes!(JSON_TOGGLE.as_str(), 
-                                             collapsed_class(collapsed.deref().get(index).unwrap_or(&option.collapsable).clone()))
+                                    classes!(JSON_TOGGLE.as_str(), collapsed_class(collapsed[index]))
                                 } onclick={onclick.clone()}>{key_repr}</a>
                             }
                         } else {
                             key_repr
                         };
                         key_count -= 1;
+                        let child = &children[index];
                         html! {
-                            <li key={index}>
+                            <li key={index} class={classes!(dimmed_class(PathSegment::Key(key.clone())), change_class(child), violation_class(child, &schema))}
+                                title={change_title(child)}>
+                                if let Some(shadowed) = shadowed {
+                                    {shadowed_html(key, shadowed)}
+                                }
                                 {key_repr}
+                                {shadowed.map(duplicate_badge)}
                                 {": "}
-                                <Render value={Rc::new(value.clone())} option={option} 
-                                        father_collapsed={collapsed.deref().get(index).unwrap_or(&option.collapsable).clone()}
+                                <Render value={child.clone()} option={option}
+                                        father_collapsed={collapsed[index]}
                                         {onclick}
+                                        scroll_target={child_scroll_target(index)}
                                 />
                                 if key_count > 0 {
                                     {","}
                                 }
+                                {edit.map(|edit| remove_button(edit, child_pointer(PathSegment::Key(key.clone()))))}
                             </li>
                         }
                     })
                     .collect::<Vec<_>>();
-                let total = object.len();
-                let placeholder = format!("{} {}", total, if total > 1 { "items" } else { "item" });
+                result.extend(show_more);
+                result.extend(edit.map(|edit| html! { <li key="add">{add_button(edit, value_ref, value)}</li> }));
+                let placeholder = placeholder_text(object.len());
                 html! {
                     <>
                         {"{"}
@@ -243,7 +982,7 @@ fn render(props: &RenderProps) -> Html {
                 }
             } else {
                 html! {
-                    <>{"{}"}</>
+                    <>{"{}"}{edit.map(|edit| add_button(edit, value_ref, value))}</>
                 }
             }
         }
@@ -251,7 +990,10 @@ fn render(props: &RenderProps) -> Html {
     html! {
         <>
             {arr}
+            {annotations_html(value_ref)}
             {element}
+            {repairs_html(value_ref)}
+            {violations_html(value_ref, &schema)}
         </>
     }
 }
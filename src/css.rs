@@ -45,6 +45,28 @@ pub static JSON_LITERAL: Lazy<String> = Lazy::new(|| {
     style.get_class_name().to_string()
 });
 
+pub static JSON_DATETIME: Lazy<String> = Lazy::new(|| {
+    let style = style!("color: #B25B00;").unwrap();
+    style.get_class_name().to_string()
+});
+
+pub static JSON_INVALID: Lazy<String> = Lazy::new(|| {
+    let style = style!("color: #c00;").unwrap();
+    style.get_class_name().to_string()
+});
+
+pub static JSON_LINE_NUMBER: Lazy<String> = Lazy::new(|| {
+    let style = style!(r#"
+        display: inline-block;
+        min-width: 2.5em;
+        margin-right: 0.8em;
+        text-align: right;
+        color: #999;
+        user-select: none;
+    "#).unwrap();
+    style.get_class_name().to_string()
+});
+
 pub static JSON_TOGGLE: Lazy<String> = Lazy::new(|| {
     let style = style!(r#"
         position: relative;
@@ -86,6 +108,234 @@ pub static JSON_PLACEHOLDER: Lazy<String> = Lazy::new(|| {
     style.get_class_name().to_string()
 });
 
+pub static JSON_VIRTUAL_ROW: Lazy<String> = Lazy::new(|| {
+    let style = style!(r#"
+        white-space: nowrap;
+        box-sizing: border-box;
+    "#).unwrap();
+    style.get_class_name().to_string()
+});
+
+pub static JSON_MATCH: Lazy<String> = Lazy::new(|| {
+    let style = style!(r#"
+        background-color: #fff3a3;
+        color: inherit;
+        padding: 0;
+    "#).unwrap();
+    style.get_class_name().to_string()
+});
+
+pub static JSON_MATCH_CURRENT: Lazy<String> = Lazy::new(|| {
+    let style = style!(r#"
+        background-color: #ffb347;
+        color: inherit;
+        padding: 0;
+    "#).unwrap();
+    style.get_class_name().to_string()
+});
+
+pub static JSON_TOOLBAR: Lazy<String> = Lazy::new(|| {
+    let style = style!(r#"
+        display: flex;
+        align-items: center;
+        margin-top: 0.5em;
+        font-size: 14px;
+        & input[type="search"] {
+            flex: 0 1 20em;
+            padding: 2px 6px;
+            border: 1px solid #aaa;
+            border-radius: 3px;
+        }
+        & input[type="number"] {
+            width: 4em;
+            margin-left: 4px;
+            padding: 2px 6px;
+            border: 1px solid #aaa;
+            border-radius: 3px;
+        }
+    "#).unwrap();
+    style.get_class_name().to_string()
+});
+
+pub static JSON_TOOLBAR_BUTTON: Lazy<String> = Lazy::new(|| {
+    let style = style!(r#"
+        margin-left: 4px;
+        padding: 0 6px;
+        border: 1px solid #aaa;
+        border-radius: 3px;
+        background-color: #f8f8f8;
+        &:disabled {
+            color: #ccc;
+        }
+    "#).unwrap();
+    style.get_class_name().to_string()
+});
+
+pub static JSON_DIMMED: Lazy<String> = Lazy::new(|| {
+    let style = style!("opacity: 0.35;").unwrap();
+    style.get_class_name().to_string()
+});
+
+pub static JSON_BADGE: Lazy<String> = Lazy::new(|| {
+    let style = style!(r#"
+        margin-left: 0.4em;
+        padding: 0 4px;
+        border-radius: 3px;
+        font-size: 0.8em;
+        color: #fff;
+        background-color: #d9822b;
+        cursor: help;
+    "#).unwrap();
+    style.get_class_name().to_string()
+});
+
+pub static JSON_ANNOTATION: Lazy<String> = Lazy::new(|| {
+    let style = style!(r#"
+        margin-right: 0.4em;
+        padding: 0 4px;
+        border: 1px solid #aaa;
+        border-radius: 3px;
+        font-size: 0.8em;
+        color: #666;
+    "#).unwrap();
+    style.get_class_name().to_string()
+});
+
+pub static JSON_REPAIR: Lazy<String> = Lazy::new(|| {
+    let style = style!(r#"
+        margin-left: 0.4em;
+        padding: 0 4px;
+        border-radius: 3px;
+        font-size: 0.8em;
+        color: #fff;
+        background-color: #7b5ea7;
+        cursor: help;
+    "#).unwrap();
+    style.get_class_name().to_string()
+});
+
+pub static JSON_REPAIR_LIST: Lazy<String> = Lazy::new(|| {
+    let style = style!(r#"
+        margin: 0.5em 0;
+        font-size: 0.9em;
+        color: #7b5ea7;
+    "#).unwrap();
+    style.get_class_name().to_string()
+});
+
+pub static JSON_DIFF_ADDED: Lazy<String> = Lazy::new(|| {
+    let style = style!("background-color: #e6ffed;").unwrap();
+    style.get_class_name().to_string()
+});
+
+pub static JSON_DIFF_REMOVED: Lazy<String> = Lazy::new(|| {
+    let style = style!(r#"
+        background-color: #ffeef0;
+        text-decoration: line-through;
+    "#).unwrap();
+    style.get_class_name().to_string()
+});
+
+pub static JSON_DIFF_CHANGED: Lazy<String> = Lazy::new(|| {
+    let style = style!("background-color: #fff5b1;").unwrap();
+    style.get_class_name().to_string()
+});
+
+pub static JSON_DIFF_MOVED: Lazy<String> = Lazy::new(|| {
+    let style = style!(r#"
+        background-color: #f1f8ff;
+        box-shadow: inset 3px 0 0 #0366d6;
+    "#).unwrap();
+    style.get_class_name().to_string()
+});
+
+pub static JSON_DIFF_LIST: Lazy<String> = Lazy::new(|| {
+    let style = style!(r#"
+        margin: 0.5em 0;
+        font-size: 0.9em;
+    "#).unwrap();
+    style.get_class_name().to_string()
+});
+
+pub static JSON_SCHEMA_INVALID: Lazy<String> = Lazy::new(|| {
+    let style = style!(r#"
+        background-color: #fff0f0;
+        box-shadow: inset 3px 0 0 #d73a49;
+    "#).unwrap();
+    style.get_class_name().to_string()
+});
+
+pub static JSON_SCHEMA_ERROR: Lazy<String> = Lazy::new(|| {
+    let style = style!(r#"
+        margin-left: 0.4em;
+        padding: 0 4px;
+        border-radius: 3px;
+        font-size: 0.8em;
+        color: #fff;
+        background-color: #d73a49;
+        cursor: help;
+    "#).unwrap();
+    style.get_class_name().to_string()
+});
+
+pub static JSON_SCHEMA_LIST: Lazy<String> = Lazy::new(|| {
+    let style = style!(r#"
+        margin: 0.5em 0;
+        font-size: 0.9em;
+        color: #d73a49;
+    "#).unwrap();
+    style.get_class_name().to_string()
+});
+
+pub static JSON_EDITABLE: Lazy<String> = Lazy::new(|| {
+    let style = style!(r#"
+        cursor: text;
+        &:hover {
+            outline: 1px dashed #aaa;
+        }
+    "#).unwrap();
+    style.get_class_name().to_string()
+});
+
+pub static JSON_EDIT_INPUT: Lazy<String> = Lazy::new(|| {
+    let style = style!(r#"
+        font: inherit;
+        padding: 0 2px;
+        border: 1px solid #0366d6;
+    "#).unwrap();
+    style.get_class_name().to_string()
+});
+
+pub static JSON_EDIT_BUTTON: Lazy<String> = Lazy::new(|| {
+    let style = style!(r#"
+        margin-left: 0.4em;
+        color: #bbb;
+        text-decoration: none;
+        &:hover {
+            color: #0366d6;
+        }
+    "#).unwrap();
+    style.get_class_name().to_string()
+});
+
+pub static JSON_SHADOWED: Lazy<String> = Lazy::new(|| {
+    let style = style!(r#"
+        color: #999;
+        text-decoration: line-through;
+    "#).unwrap();
+    style.get_class_name().to_string()
+});
+
+pub static JSON_ERROR: Lazy<String> = Lazy::new(|| {
+    let style = style!(r#"
+        display: block;
+        margin-top: 0.5em;
+        color: #c00;
+        white-space: pre-wrap;
+    "#).unwrap();
+    style.get_class_name().to_string()
+});
+
 pub static OVERLAY_CSS: Lazy<String> = Lazy::new(|| {
     let style = style!(
         r#"
@@ -0,0 +1,246 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::rc::Rc;
+use serde_json::{Map, Value};
+use yew::prelude::*;
+use crate::core::RootRender;
+use crate::document::{PathSegment, SourceInfo, ValueRef};
+use crate::JsonViewerOption;
+
+/// How elements of two arrays are paired up.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum ArrayMatch {
+    /// Elements at the same index are compared.
+    #[default]
+    Index,
+    /// Object elements with equal values of this field are compared, wherever they are; elements
+    /// that changed their relative order are reported as moved.
+    Key(String),
+}
+
+/// How the value at a pointer of the merged tree differs between the old and the new document.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Change {
+    Added,
+    Removed,
+    /// Holds the old value.
+    Changed(Value),
+    /// Holds the index in the old array.
+    Moved(usize),
+}
+
+impl Change {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Change::Added => "added",
+            Change::Removed => "removed",
+            Change::Changed(_) => "changed",
+            Change::Moved(_) => "moved",
+        }
+    }
+
+    pub fn description(&self) -> String {
+        match self {
+            Change::Changed(old) => {
+                let old = old.to_string();
+                match old.char_indices().nth(200) {
+                    Some((index, _)) => format!("was: {}…", &old[..index]),
+                    None => format!("was: {}", old),
+                }
+            }
+            Change::Moved(index) => format!("moved from index {}", index),
+            change => change.name().to_string(),
+        }
+    }
+}
+
+#[derive(PartialEq, Properties)]
+pub struct JsonDiffViewerProps {
+    pub old: ValueRef,
+    pub new: ValueRef,
+    #[prop_or_default]
+    pub array_match: ArrayMatch,
+    #[prop_or_default]
+    pub option: Rc<JsonViewerOption>,
+}
+
+/// Renders `new` with the members and elements removed since `old` put back in, and every
+/// difference marked; hovering a changed value shows the old one.
+#[function_component(JsonDiffViewer)]
+pub fn json_diff_viewer(props: &JsonDiffViewerProps) -> Html {
+    let JsonDiffViewerProps { old, new, array_match, option } = props;
+    let merged = use_memo((old.clone(), new.clone(), array_match.clone()), |(old, new, array_match)| {
+        diff(old.get(), new.get(), array_match)
+    });
+    html! {
+        <RootRender value={(*merged).clone()} option={option.clone()}/>
+    }
+}
+
+/// Merges `old` and `new` into one tree: the new document plus the removed members and elements,
+/// with every difference recorded in `SourceInfo::changes`.
+pub fn diff(old: &Value, new: &Value, matching: &ArrayMatch) -> ValueRef {
+    let mut changes = BTreeMap::new();
+    let merged = merge(old, new, "", matching, &mut changes);
+    ValueRef::with_source(merged, SourceInfo { changes, ..Default::default() })
+}
+
+fn merge(old: &Value, new: &Value, pointer: &str, matching: &ArrayMatch, changes: &mut BTreeMap<String, Change>) -> Value {
+    match (old, new) {
+        (Value::Object(old_object), Value::Object(new_object)) => {
+            let mut result = Map::new();
+            for (key, new_value) in new_object {
+                let child = format!("{}/{}", pointer, PathSegment::Key(key.clone()));
+                let value = match old_object.get(key) {
+                    Some(old_value) => merge(old_value, new_value, &child, matching, changes),
+                    None => {
+                        changes.insert(child, Change::Added);
+                        new_value.clone()
+                    }
+                };
+                result.insert(key.clone(), value);
+            }
+            for (key, old_value) in old_object.iter().filter(|(key, _)| !new_object.contains_key(*key)) {
+                changes.insert(format!("{}/{}", pointer, PathSegment::Key(key.clone())), Change::Removed);
+                result.insert(key.clone(), old_value.clone());
+            }
+            Value::Object(result)
+        }
+        (Value::Array(old_arr), Value::Array(new_arr)) => {
+            let pairs = match matching {
+                ArrayMatch::Index => (0..new_arr.len()).map(|index| (index < old_arr.len()).then_some(index)).collect(),
+                ArrayMatch::Key(field) => match_by_key(old_arr, new_arr, field),
+            };
+            let moved = moved_indices(&pairs);
+            let mut result = vec![];
+            for (new_index, (old_index, new_value)) in pairs.iter().zip(new_arr).enumerate() {
+                let child = format!("{}/{}", pointer, PathSegment::Index(new_index));
+                let value = match old_index {
+                    Some(old_index) => {
+                        let value = merge(&old_arr[*old_index], new_value, &child, matching, changes);
+                        if moved.contains(old_index) {
+                            changes.entry(child).or_insert(Change::Moved(*old_index));
+                        }
+                        value
+                    }
+                    None => {
+                        changes.insert(child, Change::Added);
+                        new_value.clone()
+                    }
+                };
+                result.push(value);
+            }
+            let mut matched = vec![false; old_arr.len()];
+            pairs.iter().flatten().for_each(|index| matched[*index] = true);
+            for (old_value, _) in old_arr.iter().zip(matched).filter(|(_, matched)| !matched) {
+                changes.insert(format!("{}/{}", pointer, PathSegment::Index(result.len())), Change::Removed);
+                result.push(old_value.clone());
+            }
+            Value::Array(result)
+        }
+        (old, new) if old == new => new.clone(),
+        (old, new) => {
+            changes.insert(pointer.to_string(), Change::Changed(old.clone()));
+            new.clone()
+        }
+    }
+}
+
+/// For every new element, the index of the old element it pairs with. Elements without the key
+/// field pair up with the remaining keyless elements in order.
+fn match_by_key(old_arr: &[Value], new_arr: &[Value], field: &str) -> Vec<Option<usize>> {
+    let key_of = |value: &Value| value.get(field).map(Value::to_string);
+    let mut by_key = HashMap::<String, Vec<usize>>::new();
+    let mut keyless = vec![];
+    for (index, value) in old_arr.iter().enumerate().rev() {
+        match key_of(value) {
+            Some(key) => by_key.entry(key).or_default().push(index),
+            None => keyless.push(index),
+        }
+    }
+    new_arr.iter().map(|value| match key_of(value) {
+        Some(key) => by_key.get_mut(&key).and_then(Vec::pop),
+        None => keyless.pop(),
+    }).collect()
+}
+
+/// Old indices of paired elements outside the longest run that kept its relative order.
+fn moved_indices(pairs: &[Option<usize>]) -> HashSet<usize> {
+    let indices = pairs.iter().flatten().copied().collect::<Vec<_>>();
+    // patience sorting: tails[k] is the position in `indices` ending the best increasing run of length k + 1
+    let mut tails: Vec<usize> = vec![];
+    let mut previous = vec![None; indices.len()];
+    for (position, index) in indices.iter().enumerate() {
+        let length = tails.partition_point(|tail| indices[*tail] < *index);
+        previous[position] = length.checked_sub(1).map(|length| tails[length]);
+        if length == tails.len() {
+            tails.push(position);
+        } else {
+            tails[length] = position;
+        }
+    }
+    let mut kept = vec![false; indices.len()];
+    let mut current = tails.last().copied();
+    while let Some(position) = current {
+        kept[position] = true;
+        current = previous[position];
+    }
+    indices.into_iter().zip(kept).filter(|(_, kept)| !kept).map(|(index, _)| index).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use super::*;
+
+    fn changes(old: Value, new: Value, matching: ArrayMatch) -> (Value, Vec<(String, Change)>) {
+        let merged = diff(&old, &new, &matching);
+        (merged.get().clone(), merged.all_changes().clone().into_iter().collect())
+    }
+
+    #[test]
+    fn objects_and_scalars() {
+        let (merged, changes) = changes(json!({"a": 1, "b": 2, "c": {"d": true}}), json!({"a": 1, "c": {"d": false}, "e": 3}), ArrayMatch::Index);
+        assert_eq!(merged, json!({"a": 1, "c": {"d": false}, "e": 3, "b": 2}));
+        assert_eq!(changes, [
+            ("/b".to_string(), Change::Removed),
+            ("/c/d".to_string(), Change::Changed(json!(true))),
+            ("/e".to_string(), Change::Added),
+        ]);
+    }
+
+    #[test]
+    fn arrays_by_index() {
+        let (merged, changes) = changes(json!([1, 2, 3]), json!([1, 5]), ArrayMatch::Index);
+        assert_eq!(merged, json!([1, 5, 3]));
+        assert_eq!(changes, [("/1".to_string(), Change::Changed(json!(2))), ("/2".to_string(), Change::Removed)]);
+    }
+
+    #[test]
+    fn arrays_by_key() {
+        let old = json!([{"id": 1, "v": "a"}, {"id": 2}, {"id": 3}, {"x": 0}]);
+        let new = json!([{"id": 3}, {"id": 1, "v": "b"}, {"id": 4}, {"x": 0}]);
+        let (merged, changes) = changes(old, new, ArrayMatch::Key("id".to_string()));
+        assert_eq!(merged, json!([{"id": 3}, {"id": 1, "v": "b"}, {"id": 4}, {"x": 0}, {"id": 2}]));
+        assert_eq!(changes, [
+            ("/0".to_string(), Change::Moved(2)),
+            ("/1/v".to_string(), Change::Changed(json!("a"))),
+            ("/2".to_string(), Change::Added),
+            ("/4".to_string(), Change::Removed),
+        ]);
+    }
+
+    #[test]
+    fn match_by_key_pairs_duplicates_and_keyless_elements_in_order() {
+        let old = [json!({"k": 1}), json!({"k": 1}), json!(0), json!(1)];
+        let new = [json!(1), json!({"k": 1}), json!({"k": 1}), json!({"k": 1}), json!({"k": 2})];
+        assert_eq!(match_by_key(&old, &new, "k"), [Some(2), Some(0), Some(1), None, None]);
+    }
+
+    #[test]
+    fn moved_indices_keeps_the_longest_ordered_run() {
+        assert!(moved_indices(&[Some(0), None, Some(1), Some(2)]).is_empty());
+        assert_eq!(moved_indices(&[Some(2), Some(0), Some(1)]), HashSet::from([2]));
+        assert_eq!(moved_indices(&[Some(3), Some(0), Some(1), Some(2), None]), HashSet::from([3]));
+        assert_eq!(moved_indices(&[Some(1), Some(0)]).len(), 1);
+    }
+}
@@ -0,0 +1,493 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{Display, Formatter};
+use std::rc::Rc;
+use serde_json::Value;
+use crate::diff::Change;
+use crate::patch::PatchOperation;
+use crate::repair::Repair;
+
+static NULL: Value = Value::Null;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum PathSegment {
+    Index(usize),
+    Key(String),
+}
+
+impl Display for PathSegment {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PathSegment::Index(index) => write!(f, "{}", index),
+            PathSegment::Key(key) => write!(f, "{}", key.replace('~', "~0").replace('/', "~1")),
+        }
+    }
+}
+
+struct PathNode {
+    parent: JsonPath,
+    segment: PathSegment,
+    depth: usize,
+}
+
+/// A path from the document root, stored as a linked list so that a child path shares its parent.
+#[derive(Clone, Default)]
+pub struct JsonPath(Option<Rc<PathNode>>);
+
+impl JsonPath {
+    pub fn root() -> Self {
+        Self(None)
+    }
+
+    pub fn is_root(&self) -> bool {
+        self.0.is_none()
+    }
+
+    pub fn child(&self, segment: PathSegment) -> Self {
+        Self(Some(Rc::new(PathNode { parent: self.clone(), segment, depth: self.depth() + 1 })))
+    }
+
+    pub fn depth(&self) -> usize {
+        self.0.as_ref().map(|node| node.depth).unwrap_or(0)
+    }
+
+    pub fn parent(&self) -> Option<&JsonPath> {
+        self.0.as_ref().map(|node| &node.parent)
+    }
+
+    pub fn last(&self) -> Option<&PathSegment> {
+        self.0.as_ref().map(|node| &node.segment)
+    }
+
+    pub fn segments(&self) -> Vec<PathSegment> {
+        let mut result = vec![];
+        let mut current = self;
+        while let Some(node) = &current.0 {
+            result.push(node.segment.clone());
+            current = &node.parent;
+        }
+        result.reverse();
+        result
+    }
+
+    pub fn from_segments(segments: impl IntoIterator<Item = PathSegment>) -> Self {
+        segments.into_iter().fold(Self::root(), |path, segment| path.child(segment))
+    }
+
+    /// Whether both are the same node. O(1), unlike `==`: equal paths built separately are not the
+    /// same node.
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        match (&self.0, &other.0) {
+            (None, None) => true,
+            (Some(a), Some(b)) => Rc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+
+    pub fn resolve<'a>(&self, root: &'a Value) -> Option<&'a Value> {
+        match &self.0 {
+            None => Some(root),
+            Some(node) => match (node.parent.resolve(root)?, &node.segment) {
+                (Value::Array(arr), PathSegment::Index(index)) => arr.get(*index),
+                (Value::Object(object), PathSegment::Key(key)) => object.get(key),
+                _ => None,
+            },
+        }
+    }
+}
+
+impl PartialEq for JsonPath {
+    fn eq(&self, other: &Self) -> bool {
+        match (&self.0, &other.0) {
+            (None, None) => true,
+            (Some(a), Some(b)) => Rc::ptr_eq(a, b) || (a.depth == b.depth && a.segment == b.segment && a.parent == b.parent),
+            _ => false,
+        }
+    }
+}
+
+/// Formats the path as a JSON Pointer (RFC 6901).
+impl Display for JsonPath {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if let Some(node) = &self.0 {
+            write!(f, "{}/{}", node.parent, node.segment)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Debug for JsonPath {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "JsonPath({:?})", self.to_string())
+    }
+}
+
+/// Values shadowed by a later occurrence of the same key, keyed by the JSON Pointer of the member,
+/// in source order.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DuplicateKeys(BTreeMap<String, Vec<Value>>);
+
+impl DuplicateKeys {
+    pub fn get(&self, pointer: &str) -> Option<&[Value]> {
+        self.0.get(pointer).map(Vec::as_slice)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn push(&mut self, pointer: String, value: Value) {
+        self.0.entry(pointer).or_default().push(value);
+    }
+
+    pub fn extend(&mut self, other: DuplicateKeys) {
+        for (pointer, values) in other.0 {
+            self.0.entry(pointer).or_default().extend(values);
+        }
+    }
+
+    pub fn remove_descendants(&mut self, pointer: &str) {
+        let prefix = format!("{}/", pointer);
+        self.0.retain(|key, _| !key.starts_with(prefix.as_str()));
+    }
+}
+
+/// What the source text said about a document beyond its JSON value.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SourceInfo {
+    pub duplicates: DuplicateKeys,
+    /// Pointers of strings that were datetimes in the source, e.g. TOML datetimes.
+    pub datetimes: BTreeSet<String>,
+    /// Source line of each NDJSON record.
+    pub lines: BTreeMap<String, usize>,
+    /// NDJSON records that failed to parse, with the error; their value is the raw line.
+    pub errors: BTreeMap<String, String>,
+    /// Length of each binary string, whose value is its hex encoding.
+    pub bytes: BTreeMap<String, usize>,
+    /// CBOR tags and MessagePack extension types wrapping a value.
+    pub annotations: BTreeMap<String, Vec<String>>,
+    /// Faults fixed by the lenient parser, keyed by the pointer of the value they were found in.
+    pub repairs: BTreeMap<String, Vec<Repair>>,
+    /// Differences from the old document when this is a merged diff tree.
+    pub changes: BTreeMap<String, Change>,
+}
+
+impl SourceInfo {
+    /// Re-keys the info for `operation`: entries follow their values when array elements shift or
+    /// members move, and are dropped with the values the operation removes or replaces. `is_array`
+    /// tells whether the container at a pointer is an array in the document before the operation,
+    /// so patches of several operations are applied one at a time against each intermediate document.
+    pub fn apply(&mut self, operation: &PatchOperation, is_array: impl Fn(&str) -> bool) {
+        let source = std::mem::take(self);
+        let relocate = |pointer: &str| relocate(pointer, operation, &is_array);
+        let repairs = rekey(source.repairs, relocate).into_iter()
+            .map(|(pointer, repairs)| {
+                let repairs = repairs.into_iter().map(|repair| Repair { pointer: pointer.clone(), ..repair }).collect();
+                (pointer, repairs)
+            })
+            .collect();
+        *self = SourceInfo {
+            duplicates: DuplicateKeys(rekey(source.duplicates.0, relocate)),
+            datetimes: source.datetimes.into_iter().filter_map(|pointer| relocate(&pointer)).collect(),
+            lines: rekey(source.lines, relocate),
+            errors: rekey(source.errors, relocate),
+            bytes: rekey(source.bytes, relocate),
+            annotations: rekey(source.annotations, relocate),
+            repairs,
+            changes: rekey(source.changes, relocate),
+        };
+    }
+
+    /// The info of the value at `pointer`, keyed relative to it as if it were the whole document.
+    pub fn subtree(&self, pointer: &str) -> Self {
+        let relocate = |key: &str| strip_pointer(key, pointer).map(str::to_string);
+        let repairs = rekey(self.repairs.clone(), relocate).into_iter()
+            .map(|(pointer, repairs)| {
+                let repairs = repairs.into_iter().map(|repair| Repair { pointer: pointer.clone(), ..repair }).collect();
+                (pointer, repairs)
+            })
+            .collect();
+        SourceInfo {
+            duplicates: DuplicateKeys(rekey(self.duplicates.0.clone(), relocate)),
+            datetimes: self.datetimes.iter().filter_map(|key| relocate(key)).collect(),
+            lines: rekey(self.lines.clone(), relocate),
+            errors: rekey(self.errors.clone(), relocate),
+            bytes: rekey(self.bytes.clone(), relocate),
+            annotations: rekey(self.annotations.clone(), relocate),
+            repairs,
+            changes: rekey(self.changes.clone(), relocate),
+        }
+    }
+}
+
+fn rekey<T>(map: BTreeMap<String, T>, relocate: impl Fn(&str) -> Option<String>) -> BTreeMap<String, T> {
+    map.into_iter().filter_map(|(pointer, value)| Some((relocate(&pointer)?, value))).collect()
+}
+
+/// The rest of `pointer` if it is `prefix` or below it.
+fn strip_pointer<'a>(pointer: &'a str, prefix: &str) -> Option<&'a str> {
+    pointer.strip_prefix(prefix).filter(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+/// Adds `delta` to the index of the element of `array` that `pointer` is in, if that index is at
+/// least `from`.
+fn shift_index(pointer: &str, array: &str, from: usize, delta: isize) -> String {
+    let Some(rest) = strip_pointer(pointer, array).and_then(|rest| rest.strip_prefix('/')) else {
+        return pointer.to_string();
+    };
+    let (token, rest) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+    match token.parse::<usize>() {
+        Ok(index) if index >= from => format!("{}/{}{}", array, index.saturating_add_signed(delta), rest),
+        _ => pointer.to_string(),
+    }
+}
+
+fn removed(pointer: &str, path: &str, is_array: &impl Fn(&str) -> bool) -> Option<String> {
+    if strip_pointer(pointer, path).is_some() {
+        return None;
+    }
+    match path.rsplit_once('/') {
+        Some((parent, token)) if is_array(parent) => match token.parse::<usize>() {
+            Ok(index) => Some(shift_index(pointer, parent, index + 1, -1)),
+            Err(_) => Some(pointer.to_string()),
+        },
+        _ => Some(pointer.to_string()),
+    }
+}
+
+fn inserted(pointer: &str, path: &str, is_array: &impl Fn(&str) -> bool) -> Option<String> {
+    match path.rsplit_once('/') {
+        Some((parent, token)) if is_array(parent) => match token.parse::<usize>() {
+            Ok(index) => Some(shift_index(pointer, parent, index, 1)),
+            // appended with "-"
+            Err(_) => Some(pointer.to_string()),
+        },
+        _ if strip_pointer(pointer, path).is_some() => None,
+        _ => Some(pointer.to_string()),
+    }
+}
+
+/// Where the value at `pointer` is after `operation`, or `None` if it is gone.
+fn relocate(pointer: &str, operation: &PatchOperation, is_array: &impl Fn(&str) -> bool) -> Option<String> {
+    match operation {
+        PatchOperation::Add { path, .. } => inserted(pointer, path, is_array),
+        PatchOperation::Remove { path } => removed(pointer, path, is_array),
+        PatchOperation::Replace { path, .. } => strip_pointer(pointer, path).is_none().then(|| pointer.to_string()),
+        PatchOperation::Move { from, path } if from == path => Some(pointer.to_string()),
+        PatchOperation::Move { from, path } => match strip_pointer(pointer, from) {
+            Some(rest) => Some(format!("{}{}", path, rest)),
+            None => inserted(&removed(pointer, from, is_array)?, path, is_array),
+        },
+    }
+}
+
+/// A cheap handle to a value inside a shared, immutable document.
+#[derive(Clone)]
+pub struct ValueRef {
+    root: Rc<Value>,
+    source: Rc<SourceInfo>,
+    path: JsonPath,
+}
+
+impl ValueRef {
+    pub fn new(value: Value) -> Self {
+        Self::from(Rc::new(value))
+    }
+
+    pub fn with_source(value: Value, source: SourceInfo) -> Self {
+        Self { root: Rc::new(value), source: Rc::new(source), path: JsonPath::root() }
+    }
+
+    pub fn root(&self) -> &Rc<Value> {
+        &self.root
+    }
+
+    pub fn source(&self) -> &SourceInfo {
+        &self.source
+    }
+
+    pub fn duplicates(&self) -> &DuplicateKeys {
+        &self.source.duplicates
+    }
+
+    pub fn is_datetime(&self) -> bool {
+        !self.source.datetimes.is_empty() && self.source.datetimes.contains(&self.path.to_string())
+    }
+
+    pub fn line(&self) -> Option<usize> {
+        if self.source.lines.is_empty() {
+            return None;
+        }
+        self.source.lines.get(&self.path.to_string()).copied()
+    }
+
+    pub fn error(&self) -> Option<&str> {
+        if self.source.errors.is_empty() {
+            return None;
+        }
+        self.source.errors.get(&self.path.to_string()).map(String::as_str)
+    }
+
+    pub fn byte_length(&self) -> Option<usize> {
+        if self.source.bytes.is_empty() {
+            return None;
+        }
+        self.source.bytes.get(&self.path.to_string()).copied()
+    }
+
+    pub fn annotations(&self) -> &[String] {
+        if self.source.annotations.is_empty() {
+            return &[];
+        }
+        self.source.annotations.get(&self.path.to_string()).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    pub fn repairs(&self) -> &[Repair] {
+        if self.source.repairs.is_empty() {
+            return &[];
+        }
+        self.source.repairs.get(&self.path.to_string()).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Every repair in the document, in source order.
+    pub fn all_repairs(&self) -> Vec<&Repair> {
+        let mut repairs = self.source.repairs.values().flatten().collect::<Vec<_>>();
+        repairs.sort_by_key(|repair| (repair.line, repair.column));
+        repairs
+    }
+
+    pub fn change(&self) -> Option<&Change> {
+        if self.source.changes.is_empty() {
+            return None;
+        }
+        self.source.changes.get(&self.path.to_string())
+    }
+
+    pub fn all_changes(&self) -> &BTreeMap<String, Change> {
+        &self.source.changes
+    }
+
+    pub fn path(&self) -> &JsonPath {
+        &self.path
+    }
+
+    /// The value, or `None` if the path doesn't resolve in the document. Walks the path from the
+    /// root, so callers should resolve once and pass the value on.
+    pub fn resolve(&self) -> Option<&Value> {
+        self.path.resolve(&self.root)
+    }
+
+    /// Like `resolve`, for refs that were built from the document they point into.
+    pub fn get(&self) -> &Value {
+        let value = self.resolve();
+        debug_assert!(value.is_some(), "{:?} doesn't resolve in its document", self.path);
+        value.unwrap_or(&NULL)
+    }
+
+    pub fn child(&self, segment: PathSegment) -> Self {
+        Self { root: self.root.clone(), source: self.source.clone(), path: self.path.child(segment) }
+    }
+
+    /// This value as the root of a document of its own, e.g. for a viewer that renders only it.
+    pub fn rerooted(&self) -> Self {
+        if self.path.is_root() {
+            return self.clone();
+        }
+        Self::with_source(self.get().clone(), self.source.subtree(&self.path.to_string()))
+    }
+}
+
+/// Identity, not value equality, so that comparing `RenderProps` is O(1). Children made by
+/// separate `child` calls compare unequal and are re-rendered.
+impl PartialEq for ValueRef {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.root, &other.root) && Rc::ptr_eq(&self.source, &other.source) && self.path.ptr_eq(&other.path)
+    }
+}
+
+impl From<Rc<Value>> for ValueRef {
+    fn from(root: Rc<Value>) -> Self {
+        Self { root, source: Default::default(), path: JsonPath::root() }
+    }
+}
+
+impl From<Value> for ValueRef {
+    fn from(value: Value) -> Self {
+        Self::new(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use super::*;
+
+    fn lines(pointers: &[&str]) -> SourceInfo {
+        SourceInfo { lines: pointers.iter().enumerate().map(|(line, pointer)| (pointer.to_string(), line)).collect(), ..Default::default() }
+    }
+
+    /// `source` after `patch`, re-keyed against each intermediate document.
+    fn patched(document: &Value, source: &SourceInfo, patch: &[PatchOperation]) -> SourceInfo {
+        let mut document = document.clone();
+        let mut source = source.clone();
+        for operation in patch {
+            source.apply(operation, |pointer| document.pointer(pointer).is_some_and(Value::is_array));
+            crate::patch::apply(&mut document, operation).unwrap();
+        }
+        source
+    }
+
+    #[test]
+    fn patched_shifts_array_elements() {
+        let document = json!([1, 2, 3]);
+        let source = lines(&["/0", "/1", "/2"]);
+        let removed = patched(&document, &source, &[PatchOperation::Remove { path: "/1".into() }]);
+        assert_eq!(removed.lines, BTreeMap::from([("/0".into(), 0), ("/1".into(), 2)]));
+        let added = patched(&document, &source, &[PatchOperation::Add { path: "/0".into(), value: Value::Null }]);
+        assert_eq!(added.lines, BTreeMap::from([("/1".into(), 0), ("/2".into(), 1), ("/3".into(), 2)]));
+    }
+
+    #[test]
+    fn patched_follows_moves_and_drops_replaced_values() {
+        let document = json!({"a": {"b": 1}, "c": 2});
+        let source = lines(&["/a", "/a/b", "/c"]);
+        let renamed = patched(&document, &source, &[PatchOperation::Move { from: "/a".into(), path: "/d".into() }]);
+        assert_eq!(renamed.lines, BTreeMap::from([("/d".into(), 0), ("/d/b".into(), 1), ("/c".into(), 2)]));
+        let replaced = patched(&document, &source, &[PatchOperation::Replace { path: "/a".into(), value: Value::Null }]);
+        assert_eq!(replaced.lines, BTreeMap::from([("/c".into(), 2)]));
+    }
+
+    #[test]
+    fn patched_sees_containers_made_by_earlier_operations() {
+        let document = json!({"x": [1, 2]});
+        let patch = [
+            PatchOperation::Move { from: "/x".into(), path: "/y".into() },
+            PatchOperation::Add { path: "/y/0".into(), value: json!(0) },
+        ];
+        // "/y" is only an array after the move, so the add shifts the moved elements
+        let moved = patched(&document, &lines(&["/x/0", "/x/1"]), &patch);
+        assert_eq!(moved.lines, BTreeMap::from([("/y/1".into(), 0), ("/y/2".into(), 1)]));
+    }
+
+    #[test]
+    fn value_refs_compare_by_identity() {
+        let value = ValueRef::new(json!({"a": [1]}));
+        let child = value.child(PathSegment::Key("a".into()));
+        assert!(child == child.clone());
+        assert!(child != value.child(PathSegment::Key("a".into())));
+        assert!(value != ValueRef::with_source((**value.root()).clone(), SourceInfo::default()));
+        assert_eq!(child.path(), value.child(PathSegment::Key("a".into())).path());
+        assert_eq!(child.resolve(), Some(&json!([1])));
+        assert_eq!(value.child(PathSegment::Key("b".into())).resolve(), None);
+    }
+
+    #[test]
+    fn rerooted_keeps_the_source_info_below_the_value() {
+        let source = lines(&["/a", "/a/b", "/a/b/0", "/ab"]);
+        let value = ValueRef::with_source(json!({"a": {"b": [1]}, "ab": 2}), source);
+        let rerooted = value.child(PathSegment::Key("a".into())).rerooted();
+        assert!(rerooted.path().is_root());
+        assert_eq!(rerooted.get(), &json!({"b": [1]}));
+        assert_eq!(rerooted.source().lines, BTreeMap::from([("".into(), 0), ("/b".into(), 1), ("/b/0".into(), 2)]));
+        assert_eq!(rerooted.child(PathSegment::Key("b".into())).line(), Some(1));
+    }
+}
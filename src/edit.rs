@@ -0,0 +1,184 @@
+use std::cell::Cell;
+use std::rc::Rc;
+use serde_json::Value;
+use yew::prelude::*;
+use web_sys::HtmlInputElement;
+use crate::css::*;
+use crate::document::{PathSegment, ValueRef};
+use crate::patch::PatchOperation;
+
+/// An edit made in the viewer: the document after the change and the operations that made it.
+#[derive(Clone, PartialEq)]
+pub struct EditEvent {
+    pub value: ValueRef,
+    pub patch: Vec<PatchOperation>,
+}
+
+/// Applies operations to the rendered document; empty unless the viewer is editable.
+#[derive(Clone, Default, PartialEq)]
+pub struct EditContext(pub Option<Callback<Vec<PatchOperation>>>);
+
+impl EditContext {
+    pub fn callback(&self) -> Option<&Callback<Vec<PatchOperation>>> {
+        self.0.as_ref()
+    }
+}
+
+/// Reads what the user typed as JSON, falling back to a plain string.
+pub fn parse_input(text: &str) -> Value {
+    serde_json::from_str(text.trim()).unwrap_or_else(|_| Value::String(text.to_string()))
+}
+
+/// Replaces the value with the edited text, read by `parse_input`.
+pub(crate) fn replace_callback(edit: &Callback<Vec<PatchOperation>>, value_ref: &ValueRef) -> Callback<String> {
+    let value_ref = value_ref.clone();
+    let edit = edit.clone();
+    Callback::from(move |text: String| {
+        let value = parse_input(&text);
+        if value != *value_ref.get() {
+            edit.emit(vec![PatchOperation::Replace { path: value_ref.path().to_string(), value }]);
+        }
+    })
+}
+
+/// Renames the member `key` of `object` in place, unless the new key is already taken.
+pub(crate) fn rename_callback(edit: &Callback<Vec<PatchOperation>>, object: &ValueRef, key: &str) -> Callback<String> {
+    let object = object.clone();
+    let key = key.to_string();
+    let edit = edit.clone();
+    Callback::from(move |new_key: String| {
+        if object.get().as_object().is_some_and(|object| !object.contains_key(&new_key)) {
+            let from = object.path().child(PathSegment::Key(key.clone())).to_string();
+            let path = object.path().child(PathSegment::Key(new_key)).to_string();
+            edit.emit(vec![PatchOperation::Move { from, path }]);
+        }
+    })
+}
+
+pub(crate) fn remove_button(edit: &Callback<Vec<PatchOperation>>, pointer: String) -> Html {
+    let onclick = edit.reform(move |e: MouseEvent| {
+        e.prevent_default();
+        vec![PatchOperation::Remove { path: pointer.clone() }]
+    });
+    html! {
+        <a href="" class={classes!(JSON_EDIT_BUTTON.as_str())} title="Remove" {onclick}>{"×"}</a>
+    }
+}
+
+/// Appends `null` to the array `container`, or a `null` member under a fresh key to the object;
+/// `value` is its resolved value.
+pub(crate) fn add_button(edit: &Callback<Vec<PatchOperation>>, container: &ValueRef, value: &Value) -> Html {
+    let segment = match value {
+        Value::Array(arr) => PathSegment::Index(arr.len()),
+        Value::Object(object) => {
+            let key = (0..).map(|n| if n == 0 { "key".to_string() } else { format!("key{}", n) })
+                .find(|key| !object.contains_key(key))
+                .unwrap();
+            PathSegment::Key(key)
+        }
+        _ => return html! {},
+    };
+    let path = container.path().child(segment).to_string();
+    let onclick = edit.reform(move |e: MouseEvent| {
+        e.prevent_default();
+        vec![PatchOperation::Add { path: path.clone(), value: Value::Null }]
+    });
+    html! {
+        <a href="" class={classes!(JSON_EDIT_BUTTON.as_str())} title="Add" {onclick}>{"+"}</a>
+    }
+}
+
+#[derive(PartialEq, Properties)]
+pub struct EditableProps {
+    /// What the input starts with.
+    pub text: String,
+    pub on_commit: Callback<String>,
+    pub children: Html,
+}
+
+/// Shows its children until double-clicked, then an input that commits on Enter or blur and
+/// cancels on Escape.
+#[function_component(Editable)]
+pub fn editable(props: &EditableProps) -> Html {
+    let EditableProps { text, on_commit, children } = props;
+    let editing = use_state(|| false);
+    if !*editing {
+        let ondblclick = Callback::from({
+            let editing = editing.clone();
+            move |e: MouseEvent| {
+                e.prevent_default();
+                editing.set(true);
+            }
+        });
+        return html! {
+            <span class={classes!(JSON_EDITABLE.as_str())} title="Double-click to edit" {ondblclick}>{children.clone()}</span>
+        };
+    }
+    let on_done = Callback::from({
+        let editing = editing.clone();
+        let on_commit = on_commit.clone();
+        move |text: Option<String>| {
+            editing.set(false);
+            if let Some(text) = text {
+                on_commit.emit(text);
+            }
+        }
+    });
+    html! {
+        <EditInput text={text.clone()} {on_done}/>
+    }
+}
+
+#[derive(PartialEq, Properties)]
+struct EditInputProps {
+    text: String,
+    /// Emits the text, or `None` when cancelled; emits once.
+    on_done: Callback<Option<String>>,
+}
+
+#[function_component(EditInput)]
+fn edit_input(props: &EditInputProps) -> Html {
+    let EditInputProps { text, on_done } = props;
+    let input = use_node_ref();
+    // removing the focused input fires blur after Enter or Escape already finished the edit
+    let done = use_memo((), |_| Cell::new(false));
+    use_effect_with((), {
+        let input = input.clone();
+        move |_| {
+            if let Some(input) = input.cast::<HtmlInputElement>() {
+                let _ = input.focus();
+                input.select();
+            }
+            || {}
+        }
+    });
+    let finish = {
+        let done = Rc::clone(&done);
+        let on_done = on_done.clone();
+        move |text: Option<String>| {
+            if !done.replace(true) {
+                on_done.emit(text);
+            }
+        }
+    };
+    let onkeydown = Callback::from({
+        let finish = finish.clone();
+        move |e: KeyboardEvent| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            match e.key().as_str() {
+                "Enter" => finish(Some(input.value())),
+                "Escape" => finish(None),
+                _ => return,
+            }
+            e.stop_propagation();
+        }
+    });
+    let onblur = Callback::from(move |e: FocusEvent| {
+        let input: HtmlInputElement = e.target_unchecked_into();
+        finish(Some(input.value()))
+    });
+    let size = text.chars().count().clamp(4, 60).to_string();
+    html! {
+        <input type="text" class={classes!(JSON_EDIT_INPUT.as_str())} value={text.clone()} {size} ref={input} {onkeydown} {onblur}/>
+    }
+}
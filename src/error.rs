@@ -0,0 +1,160 @@
+use wasm_bindgen::prelude::*;
+
+const SNIPPET_RADIUS: usize = 30;
+const CONTEXT_LINES: usize = 2;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ViewerError {
+    pub kind: &'static str,
+    pub message: String,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub snippet: Option<String>,
+}
+
+impl ViewerError {
+    pub fn parse(message: String, text: &str, line: Option<usize>, column: Option<usize>) -> Self {
+        let snippet = line.and_then(|line| snippet(text, line, column.unwrap_or(1)));
+        Self { kind: "parse", message, line, column, snippet }
+    }
+
+    pub fn from_serde_json(err: serde_json::Error, text: &str) -> Self {
+        let (line, column) = (err.line(), err.column());
+        if line == 0 {
+            Self::parse(err.to_string(), text, None, None)
+        } else {
+            // serde_json counts columns in bytes, the other parsers in characters
+            let column = text.lines().nth(line - 1).map_or(column, |content| {
+                let end = (1..=column.min(content.len())).rev().find(|end| content.is_char_boundary(*end)).unwrap_or(0);
+                content[..end].chars().count().max(1)
+            });
+            Self::parse(err.to_string(), text, Some(line), Some(column))
+        }
+    }
+
+    pub fn from_json5(err: json5::Error, text: &str) -> Self {
+        match err {
+            json5::Error::Message { msg, location } => {
+                let (line, column) = location.map(|l| (Some(l.line), Some(l.column))).unwrap_or_default();
+                Self::parse(msg, text, line, column)
+            }
+        }
+    }
+
+    pub fn from_yaml(err: serde_yaml::Error, text: &str) -> Self {
+        let (line, column) = err.location().map(|l| (Some(l.line()), Some(l.column()))).unwrap_or_default();
+        Self::parse(err.to_string(), text, line, column)
+    }
+
+    pub fn from_toml(err: toml_edit::TomlError, text: &str) -> Self {
+        let position = err.span().map(|span| {
+            let before = &text[..span.start.min(text.len())];
+            let line = before.matches('\n').count() + 1;
+            let column = before.rsplit('\n').next().unwrap_or("").chars().count() + 1;
+            (line, column)
+        });
+        let message = err.message().to_string();
+        Self::parse(message, text, position.map(|p| p.0), position.map(|p| p.1))
+    }
+
+    pub fn decode(message: String, offset: Option<usize>) -> Self {
+        let message = match offset {
+            Some(offset) => format!("{} at byte {}", message, offset),
+            None => message,
+        };
+        Self { kind: "decode", message, line: None, column: None, snippet: None }
+    }
+
+    pub fn convert(message: String) -> Self {
+        Self { kind: "convert", message, line: None, column: None, snippet: None }
+    }
+
+    pub fn search(message: String) -> Self {
+        Self { kind: "search", message, line: None, column: None, snippet: None }
+    }
+
+    pub fn jq(message: String) -> Self {
+        Self { kind: "jq", message, line: None, column: None, snippet: None }
+    }
+
+    pub fn patch(message: String) -> Self {
+        Self { kind: "patch", message, line: None, column: None, snippet: None }
+    }
+
+    pub fn schema(message: String) -> Self {
+        Self { kind: "schema", message, line: None, column: None, snippet: None }
+    }
+
+    pub fn element_not_found(id: &str) -> Self {
+        Self {
+            kind: "element_not_found",
+            message: format!("element({}) not found", id),
+            line: None,
+            column: None,
+            snippet: None,
+        }
+    }
+}
+
+impl std::fmt::Display for ViewerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl From<ViewerError> for JsValue {
+    fn from(err: ViewerError) -> Self {
+        let error = js_sys::Error::new(err.message.as_str());
+        let set = |key: &str, value: JsValue| {
+            js_sys::Reflect::set(&error, &JsValue::from_str(key), &value).unwrap();
+        };
+        set("kind", JsValue::from_str(err.kind));
+        set("line", err.line.map(|v| JsValue::from_f64(v as f64)).unwrap_or(JsValue::NULL));
+        set("column", err.column.map(|v| JsValue::from_f64(v as f64)).unwrap_or(JsValue::NULL));
+        set("snippet", err.snippet.map(|v| JsValue::from_str(v.as_str())).unwrap_or(JsValue::NULL));
+        error.into()
+    }
+}
+
+pub fn snippet(text: &str, line: usize, column: usize) -> Option<String> {
+    let content = text.lines().nth(line.checked_sub(1)?)?;
+    let chars = content.chars().collect::<Vec<_>>();
+    let column = column.saturating_sub(1).min(chars.len());
+    let start = column.saturating_sub(SNIPPET_RADIUS);
+    let end = (column + SNIPPET_RADIUS).min(chars.len());
+    Some(chars[start..end].iter().collect())
+}
+
+/// Byte offset in `text` of a 1-based `line` and character `column`, clamped to the end of the line.
+pub fn offset(text: &str, line: usize, column: usize) -> Option<usize> {
+    let start = if line <= 1 {
+        0
+    } else {
+        text.match_indices('\n').nth(line - 2)?.0 + 1
+    };
+    let content = text[start..].split('\n').next().unwrap_or_default();
+    let within = content.char_indices().nth(column.saturating_sub(1)).map_or(content.len(), |(index, _)| index);
+    Some(start + within)
+}
+
+/// The lines around `line` prefixed with their numbers, with a caret under `column`.
+pub fn caret_snippet(text: &str, line: usize, column: usize) -> Option<String> {
+    let lines = text.lines().collect::<Vec<_>>();
+    let index = line.checked_sub(1).filter(|index| *index <= lines.len())?;
+    let first = index.saturating_sub(CONTEXT_LINES);
+    let last = (index + CONTEXT_LINES).min(lines.len().saturating_sub(1));
+    let width = (last + 1).max(line).to_string().len();
+    let mut result = String::new();
+    for (number, content) in lines.iter().enumerate().take(last + 1).skip(first) {
+        result.push_str(&format!("{:>width$} | {}\n", number + 1, content));
+        if number == index {
+            // keep tabs so the caret lines up with the text above it
+            let indent = content.chars().take(column.saturating_sub(1)).map(|c| if c == '\t' { '\t' } else { ' ' }).collect::<String>();
+            result.push_str(&format!("{:>width$} | {}^\n", "", indent));
+        }
+    }
+    if index == lines.len() {
+        result.push_str(&format!("{:>width$} | ^\n", line));
+    }
+    Some(result)
+}
@@ -0,0 +1,152 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use serde_json::Value;
+use crate::error::ViewerError;
+
+/// Text formats the viewer can parse into its JSON tree.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Format {
+    #[default]
+    Json,
+    Json5,
+    Yaml,
+    Toml,
+    Ndjson,
+}
+
+impl Format {
+    pub const ALL: [Format; 5] = [Format::Json, Format::Json5, Format::Yaml, Format::Toml, Format::Ndjson];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Format::Json => "json",
+            Format::Json5 => "json5",
+            Format::Yaml => "yaml",
+            Format::Toml => "toml",
+            Format::Ndjson => "ndjson",
+        }
+    }
+
+    /// Guesses the format of a file from its extension, falling back to its content.
+    pub fn detect(file_name: &str, text: &str) -> Format {
+        let extension = file_name.rsplit_once('.').map(|(_, extension)| extension.to_ascii_lowercase());
+        match extension.as_deref() {
+            Some("json") => return Format::Json,
+            Some("json5") => return Format::Json5,
+            Some("yaml" | "yml") => return Format::Yaml,
+            Some("toml") => return Format::Toml,
+            Some("ndjson" | "jsonl") => return Format::Ndjson,
+            _ => {}
+        }
+        let is_json = |text: &str| serde_json::from_str::<serde::de::IgnoredAny>(text).is_ok();
+        if is_json(text) {
+            return Format::Json;
+        }
+        let first_line = text.lines().map(str::trim).find(|line| !line.is_empty() && !line.starts_with('#')).unwrap_or_default();
+        if is_toml_line(first_line) {
+            return Format::Toml;
+        }
+        if !first_line.starts_with(['{', '[']) {
+            return Format::Yaml;
+        }
+        let mut lines = text.lines().map(str::trim).filter(|line| !line.is_empty());
+        if lines.clone().count() > 1 && lines.all(is_json) {
+            Format::Ndjson
+        } else {
+            Format::Json5
+        }
+    }
+}
+
+/// Whether `line` is a TOML table header (`[server]`, `[[items]]`) or a `key = value` pair.
+fn is_toml_line(line: &str) -> bool {
+    let is_key = |key: &str| {
+        !key.is_empty() && key.chars().all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | ' ' | '"' | '\''))
+    };
+    if let Some(header) = line.strip_prefix('[').and_then(|line| line.split('#').next()?.trim_end().strip_suffix(']')) {
+        let header = header.strip_prefix('[').and_then(|header| header.strip_suffix(']')).unwrap_or(header);
+        return is_key(header.trim()) && !header.trim().chars().all(|c| c.is_ascii_digit());
+    }
+    line.split_once('=').is_some_and(|(key, value)| is_key(key.trim()) && !value.trim().is_empty() && !value.starts_with('='))
+}
+
+impl Display for Format {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Format {
+    type Err = ViewerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Format::ALL.into_iter()
+            .find(|format| format.name().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| ViewerError::parse(format!("unknown format: {}", s), s, None, None))
+    }
+}
+
+/// Serializes `value` as a YAML document, keeping numbers exactly as written.
+pub fn to_yaml(value: &Value) -> String {
+    let mut result = String::new();
+    write_yaml(&mut result, value, 0);
+    result.push('\n');
+    result
+}
+
+// serde_yaml only holds numbers as i64, u64 or f64, so the document is written by hand
+fn write_yaml(result: &mut String, value: &Value, indent: usize) {
+    let newline = |result: &mut String, indent: usize| {
+        result.push('\n');
+        result.push_str(&" ".repeat(indent));
+    };
+    match value {
+        Value::Null => result.push_str("null"),
+        Value::Bool(b) => result.push_str(if *b { "true" } else { "false" }),
+        Value::Number(n) => result.push_str(&n.to_string()),
+        Value::String(s) => result.push_str(&yaml_string(s)),
+        Value::Array(arr) if arr.is_empty() => result.push_str("[]"),
+        Value::Object(object) if object.is_empty() => result.push_str("{}"),
+        Value::Array(arr) => {
+            for (index, item) in arr.iter().enumerate() {
+                if index > 0 {
+                    newline(result, indent);
+                }
+                result.push_str("- ");
+                write_yaml(result, item, indent + 2);
+            }
+        }
+        Value::Object(object) => {
+            for (index, (key, item)) in object.iter().enumerate() {
+                if index > 0 {
+                    newline(result, indent);
+                }
+                result.push_str(&yaml_string(key));
+                result.push(':');
+                match item {
+                    Value::Array(arr) if !arr.is_empty() => {
+                        newline(result, indent);
+                        write_yaml(result, item, indent);
+                    }
+                    Value::Object(object) if !object.is_empty() => {
+                        newline(result, indent + 2);
+                        write_yaml(result, item, indent + 2);
+                    }
+                    _ => {
+                        result.push(' ');
+                        write_yaml(result, item, indent);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// A string as a single-line YAML scalar: serde_yaml picks plain or quoted style, and strings it
+/// would write as block scalars are double-quoted the JSON way, which YAML also reads.
+fn yaml_string(s: &str) -> String {
+    match serde_yaml::to_string(s) {
+        Ok(text) if text.trim_end_matches('\n').lines().count() == 1 => text.trim_end_matches('\n').to_string(),
+        _ => serde_json::to_string(s).unwrap(),
+    }
+}
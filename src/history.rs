@@ -0,0 +1,282 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+use serde_json::Value;
+use yew::Callback;
+use crate::error::ViewerError;
+use crate::patch::{self, PatchOperation};
+
+pub const DEFAULT_LIMIT: usize = 100;
+
+pub enum EditCommand {
+    Apply(Vec<PatchOperation>),
+    Undo,
+    Redo,
+}
+
+struct HistoryEntry {
+    patch: Vec<PatchOperation>,
+    inverse: Vec<PatchOperation>,
+}
+
+struct HistoryState {
+    undo: VecDeque<HistoryEntry>,
+    redo: Vec<HistoryEntry>,
+    limit: usize,
+    /// Runs commands on the document of the viewer the history was passed to.
+    viewer: Option<Callback<EditCommand>>,
+}
+
+/// Undo and redo stacks of a viewer's edits, kept as JSON Patch operations. Clones share the
+/// same history, so the owner of the viewer can undo and redo through its own copy.
+#[derive(Clone)]
+pub struct EditHistory(Rc<RefCell<HistoryState>>);
+
+impl EditHistory {
+    /// Keeps at most `limit` edits to undo.
+    pub fn new(limit: usize) -> Self {
+        Self(Rc::new(RefCell::new(HistoryState { undo: VecDeque::new(), redo: vec![], limit, viewer: None })))
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.0.borrow().undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.0.borrow().redo.is_empty()
+    }
+
+    pub fn undo(&self) {
+        self.send(EditCommand::Undo);
+    }
+
+    pub fn redo(&self) {
+        self.send(EditCommand::Redo);
+    }
+
+    pub(crate) fn send(&self, command: EditCommand) {
+        let viewer = self.0.borrow().viewer.clone();
+        if let Some(viewer) = viewer {
+            viewer.emit(command);
+        }
+    }
+
+    pub(crate) fn attach(&self, viewer: Callback<EditCommand>) {
+        self.0.borrow_mut().viewer = Some(viewer);
+    }
+
+    /// Detaches `viewer` unless another viewer has been attached since.
+    pub(crate) fn detach(&self, viewer: &Callback<EditCommand>) {
+        let mut state = self.0.borrow_mut();
+        if state.viewer.as_ref() == Some(viewer) {
+            state.viewer = None;
+        }
+    }
+
+    pub(crate) fn set_limit(&self, limit: usize) {
+        let mut state = self.0.borrow_mut();
+        state.limit = limit;
+        state.trim();
+    }
+
+    pub(crate) fn clear(&self) {
+        let mut state = self.0.borrow_mut();
+        state.undo.clear();
+        state.redo.clear();
+    }
+
+    /// Runs `command` on `value` and returns the operations it applied, empty when there was
+    /// nothing to undo or redo. `on_apply` sees each operation with the document it is about to be
+    /// applied to. On error `value` is left partially changed; a failed edit isn't recorded, and an
+    /// undo or redo that no longer applies is dropped so that it doesn't fail again.
+    pub(crate) fn run(
+        &self,
+        value: &mut Value,
+        command: EditCommand,
+        mut on_apply: impl FnMut(&Value, &PatchOperation),
+    ) -> Result<Vec<PatchOperation>, ViewerError> {
+        let mut state = self.0.borrow_mut();
+        let mut apply = |value: &mut Value, operation: &PatchOperation| {
+            on_apply(value, operation);
+            patch::apply(value, operation)
+        };
+        match command {
+            EditCommand::Apply(patch) => {
+                let mut inverse = patch.iter().map(|operation| apply(value, operation)).collect::<Result<Vec<_>, _>>()?;
+                inverse.reverse();
+                state.redo.clear();
+                state.undo.push_back(HistoryEntry { patch: patch.clone(), inverse });
+                state.trim();
+                Ok(patch)
+            }
+            EditCommand::Undo => {
+                let Some(entry) = state.undo.pop_back() else { return Ok(vec![]) };
+                for operation in &entry.inverse {
+                    apply(value, operation)?;
+                }
+                let applied = entry.inverse.clone();
+                state.redo.push(entry);
+                Ok(applied)
+            }
+            EditCommand::Redo => {
+                let Some(entry) = state.redo.pop() else { return Ok(vec![]) };
+                for operation in &entry.patch {
+                    apply(value, operation)?;
+                }
+                let applied = entry.patch.clone();
+                state.undo.push_back(entry);
+                state.trim();
+                Ok(applied)
+            }
+        }
+    }
+}
+
+impl HistoryState {
+    fn trim(&mut self) {
+        while self.undo.len() > self.limit {
+            self.undo.pop_front();
+        }
+    }
+}
+
+impl Default for EditHistory {
+    fn default() -> Self {
+        Self::new(DEFAULT_LIMIT)
+    }
+}
+
+impl PartialEq for EditHistory {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use super::*;
+
+    fn run(history: &EditHistory, value: &mut Value, command: EditCommand) -> Result<Vec<PatchOperation>, ViewerError> {
+        history.run(value, command, |_, _| {})
+    }
+
+    fn replace(path: &str, value: Value) -> EditCommand {
+        EditCommand::Apply(vec![PatchOperation::Replace { path: path.to_string(), value }])
+    }
+
+    #[test]
+    fn undo_and_redo() {
+        let history = EditHistory::default();
+        let mut value = json!({"a": 1});
+        run(&history, &mut value, replace("/a", json!(2))).unwrap();
+        run(&history, &mut value, replace("/a", json!(3))).unwrap();
+        assert_eq!(value, json!({"a": 3}));
+        assert_eq!(run(&history, &mut value, EditCommand::Undo).unwrap(), [PatchOperation::Replace { path: "/a".into(), value: json!(2) }]);
+        run(&history, &mut value, EditCommand::Undo).unwrap();
+        assert_eq!(value, json!({"a": 1}));
+        assert!(!history.can_undo());
+        assert!(run(&history, &mut value, EditCommand::Undo).unwrap().is_empty());
+        run(&history, &mut value, EditCommand::Redo).unwrap();
+        assert_eq!(value, json!({"a": 2}));
+        assert!(history.can_undo() && history.can_redo());
+    }
+
+    #[test]
+    fn new_edits_clear_redo() {
+        let history = EditHistory::default();
+        let mut value = json!([1]);
+        run(&history, &mut value, replace("/0", json!(2))).unwrap();
+        run(&history, &mut value, EditCommand::Undo).unwrap();
+        run(&history, &mut value, replace("/0", json!(3))).unwrap();
+        assert!(!history.can_redo());
+        assert!(run(&history, &mut value, EditCommand::Redo).unwrap().is_empty());
+        assert_eq!(value, json!([3]));
+    }
+
+    #[test]
+    fn multi_operation_patches_undo_in_reverse() {
+        let history = EditHistory::default();
+        let mut value = json!([]);
+        let patch = vec![
+            PatchOperation::Add { path: "/0".into(), value: json!(1) },
+            PatchOperation::Add { path: "/0".into(), value: json!(2) },
+        ];
+        run(&history, &mut value, EditCommand::Apply(patch)).unwrap();
+        assert_eq!(value, json!([2, 1]));
+        run(&history, &mut value, EditCommand::Undo).unwrap();
+        assert_eq!(value, json!([]));
+    }
+
+    #[test]
+    fn limit_drops_the_oldest_edits() {
+        let history = EditHistory::new(2);
+        let mut value = json!(0);
+        for n in 1..=3 {
+            run(&history, &mut value, replace("", json!(n))).unwrap();
+        }
+        run(&history, &mut value, EditCommand::Undo).unwrap();
+        run(&history, &mut value, EditCommand::Undo).unwrap();
+        assert_eq!(value, json!(1));
+        assert!(!history.can_undo());
+        history.set_limit(0);
+        assert!(!history.can_undo());
+    }
+
+    #[test]
+    fn failed_edits_are_not_recorded() {
+        let history = EditHistory::default();
+        let mut value = json!({});
+        assert!(run(&history, &mut value, replace("/missing", json!(1))).is_err());
+        assert!(!history.can_undo());
+    }
+
+    #[test]
+    fn commands_go_to_the_attached_viewer() {
+        let history = EditHistory::default();
+        history.undo();
+        let received = Rc::new(RefCell::new(vec![]));
+        history.attach(Callback::from({
+            let received = received.clone();
+            move |command: EditCommand| received.borrow_mut().push(matches!(command, EditCommand::Undo))
+        }));
+        history.clone().undo();
+        history.redo();
+        assert_eq!(*received.borrow(), [true, false]);
+    }
+
+    #[test]
+    fn detaching_keeps_a_later_viewer() {
+        let history = EditHistory::default();
+        let received = Rc::new(RefCell::new(0));
+        let first = Callback::from(|_: EditCommand| panic!("detached"));
+        let second = Callback::from({
+            let received = received.clone();
+            move |_: EditCommand| *received.borrow_mut() += 1
+        });
+        history.attach(first.clone());
+        history.attach(second.clone());
+        history.detach(&first);
+        history.undo();
+        history.detach(&second);
+        history.undo();
+        assert_eq!(*received.borrow(), 1);
+    }
+
+    #[test]
+    fn edits_that_no_longer_apply_are_dropped() {
+        let history = EditHistory::default();
+        let mut value = json!({"a": 1});
+        run(&history, &mut value, replace("/a", json!(2))).unwrap();
+        run(&history, &mut value, EditCommand::Apply(vec![PatchOperation::Add { path: "/b".into(), value: json!(3) }])).unwrap();
+        // the document changed behind the history's back
+        let mut changed = json!({"a": 2});
+        assert!(run(&history, &mut changed, EditCommand::Undo).is_err());
+        assert_eq!(run(&history, &mut value, EditCommand::Undo).unwrap(), [PatchOperation::Replace { path: "/a".into(), value: json!(1) }]);
+        assert!(!history.can_undo());
+        assert!(history.can_redo());
+        let mut changed = json!({});
+        assert!(run(&history, &mut changed, EditCommand::Redo).is_err());
+        assert!(!history.can_redo());
+    }
+}
@@ -3,6 +3,8 @@ use std::{cell::RefCell, rc::Rc};
 use crate::{
     common::{set_body_overflow_style, value_length, CopyButton},
     css::*,
+    document::ValueRef,
+    format::to_yaml,
     JsonViewer, JsonViewerOption,
 };
 use gloo::utils::{document, window};
@@ -89,19 +91,16 @@ fn dialog(props: &DialogProps) -> Html {
 
 #[derive(PartialEq, Properties)]
 pub struct ButtonControlDialogJsonViewerProps {
-    pub value: Value,
+    pub value: ValueRef,
 }
 
 #[function_component(ButtonControlDialogJsonViewer)]
 pub fn button_control_dialog_json_viewer(props: &ButtonControlDialogJsonViewerProps) -> Html {
     let ButtonControlDialogJsonViewerProps { value } = props;
     let open = use_state(|| false);
-    let mut option: JsonViewerOption = Default::default();
     let index = document()
         .get_elements_by_class_name("yew_json_viewer_dialog")
         .length() as i32;
-    option.dialog_index = Some(index);
-    let viewer = JsonViewer::new_with_option(value.clone(), option);
     let set_close = {
         let open = open.clone();
         Callback::from(move |_| {
@@ -120,18 +119,39 @@ pub fn button_control_dialog_json_viewer(props: &ButtonControlDialogJsonViewerPr
                 <div class={INTERACTION_BUTTON_CSS.as_str()}/>
             </span>
             if *open {
-                <Dialog {set_close} {index}>
-                    <span class="h5">{"JSON"}</span>
-                    <span style="vertical-align: 0.2em; margin-left: 0.2em;">
-                        <CopyButton text={value.to_string()}/>
-                    </span>
-                    {viewer.render()}
-                </Dialog>
+                <DialogJsonViewer value={value.clone()} {set_close} {index}/>
             }
         </>
     }
 }
 
+#[derive(PartialEq, Properties)]
+struct DialogJsonViewerProps {
+    value: ValueRef,
+    set_close: Callback<MouseEvent>,
+    index: i32,
+}
+
+#[function_component(DialogJsonViewer)]
+fn dialog_json_viewer(props: &DialogJsonViewerProps) -> Html {
+    let DialogJsonViewerProps { value, set_close, index } = props;
+    // the dialog's collapse pointers and expand depth count from the value it shows; the copy is
+    // only made while the dialog is open
+    let value = use_memo(value.clone(), ValueRef::rerooted);
+    let option = JsonViewerOption { dialog_index: Some(*index), ..Default::default() };
+    let viewer = JsonViewer::new_with_option((*value).clone(), option);
+    html! {
+        <Dialog set_close={set_close.clone()} index={*index}>
+            <span class="h5">{"JSON"}</span>
+            <span style="vertical-align: 0.2em; margin-left: 0.2em;">
+                <CopyButton text={value.get().to_string()}/>
+                <CopyButton text={to_yaml(value.get())} label="YAML"/>
+            </span>
+            {viewer.render()}
+        </Dialog>
+    }
+}
+
 #[derive(PartialEq, Properties)]
 struct LongTextViewerProps {
     text: String,
@@ -186,9 +206,10 @@ extern "C" {
     pub fn do_global_javascript_interaction(json_string: String) -> Option<js_sys::Function>;
 }
 
-pub fn default_interaction(use_json5: Rc<RefCell<bool>>) -> Box<dyn Fn(&Value) -> Vec<VNode>> {
+pub fn default_interaction(use_json5: Rc<RefCell<bool>>) -> Box<dyn Fn(&ValueRef) -> Vec<VNode>> {
     Box::new(
-        move |arg: &Value| -> Vec<VNode> {
+        move |value_ref: &ValueRef| -> Vec<VNode> {
+            let arg = value_ref.get();
             if with_global_javascript_interaction() {
                 if let Some(func) = do_global_javascript_interaction(arg.to_string()) {
                     let onclick: Callback<MouseEvent> = Callback::from(move |_: MouseEvent| {
@@ -227,7 +248,7 @@ pub fn default_interaction(use_json5: Rc<RefCell<bool>>) -> Box<dyn Fn(&Value) -
                             match value {
                                 Value::Object(_) | Value::Array(_) | Value::String(_) => {
                                     result.push(html! {
-                                        <ButtonControlDialogJsonViewer value={value.clone()}/>
+                                        <ButtonControlDialogJsonViewer value={ValueRef::new(value)}/>
                                     });
                                 }
                                 _ => {}
@@ -242,7 +263,7 @@ pub fn default_interaction(use_json5: Rc<RefCell<bool>>) -> Box<dyn Fn(&Value) -
                 Value::Array(_) | Value::Object(_) => {
                     if value_length(arg) > 0 {
                         result.push(html! {
-                            <ButtonControlDialogJsonViewer value={arg.clone()}/>
+                            <ButtonControlDialogJsonViewer value={value_ref.clone()}/>
                         });
                     }
                 }
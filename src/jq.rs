@@ -0,0 +1,721 @@
+use std::cmp::Ordering;
+use serde_json::{Map, Number, Value};
+use crate::error::ViewerError;
+
+#[derive(Clone, Debug, PartialEq)]
+enum Expr {
+    Identity,
+    RecurseAll,
+    Literal(Value),
+    Field(Box<Expr>, String),
+    Index(Box<Expr>, Box<Expr>),
+    Iterate(Box<Expr>),
+    Optional(Box<Expr>),
+    Pipe(Box<Expr>, Box<Expr>),
+    Comma(Box<Expr>, Box<Expr>),
+    Binary(Box<Expr>, BinaryOp, Box<Expr>),
+    Negate(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Array(Option<Box<Expr>>),
+    Object(Vec<(Expr, Expr)>),
+    Call(String, Vec<Expr>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// A parsed jq filter, supporting paths, pipes, `,`, comparisons, `and`/`or`, array and object
+/// construction and the builtins `map`, `select`, `keys`, `length`, `not`, `add`, `has`, `type` and `empty`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct JqFilter {
+    expr: Expr,
+}
+
+impl JqFilter {
+    pub fn parse(expression: &str) -> Result<Self, ViewerError> {
+        let mut parser = Parser { chars: expression.chars().collect(), pos: 0 };
+        let expr = parser.pipe()?;
+        parser.skip_whitespace();
+        if parser.pos < parser.chars.len() {
+            return Err(parser.error("unexpected character"));
+        }
+        Ok(Self { expr })
+    }
+
+    pub fn run(&self, input: &Value) -> Result<Vec<Value>, ViewerError> {
+        eval(&self.expr, input).map_err(ViewerError::jq)
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn truthy(value: &Value) -> bool {
+    !matches!(value, Value::Null | Value::Bool(false))
+}
+
+fn number(n: f64) -> Result<Value, String> {
+    if n.fract() == 0.0 && n.abs() < 9007199254740992.0 {
+        return Ok(Value::Number((n as i64).into()));
+    }
+    Number::from_f64(n).map(Value::Number).ok_or_else(|| format!("{} is not a valid number", n))
+}
+
+fn eval(expr: &Expr, input: &Value) -> Result<Vec<Value>, String> {
+    match expr {
+        Expr::Identity => Ok(vec![input.clone()]),
+        Expr::RecurseAll => {
+            let mut result = vec![];
+            let mut stack = vec![input];
+            while let Some(value) = stack.pop() {
+                result.push(value.clone());
+                match value {
+                    Value::Array(arr) => stack.extend(arr.iter().rev()),
+                    Value::Object(object) => stack.extend(object.values().rev()),
+                    _ => {}
+                }
+            }
+            Ok(result)
+        }
+        Expr::Literal(value) => Ok(vec![value.clone()]),
+        Expr::Field(target, name) => {
+            let mut result = vec![];
+            for value in eval(target, input)? {
+                result.push(index(&value, &Value::String(name.clone()))?);
+            }
+            Ok(result)
+        }
+        Expr::Index(target, key) => {
+            let mut result = vec![];
+            for value in eval(target, input)? {
+                for key in eval(key, input)? {
+                    result.push(index(&value, &key)?);
+                }
+            }
+            Ok(result)
+        }
+        Expr::Iterate(target) => {
+            let mut result = vec![];
+            for value in eval(target, input)? {
+                match value {
+                    Value::Array(arr) => result.extend(arr),
+                    Value::Object(object) => result.extend(object.into_iter().map(|(_, v)| v)),
+                    other => return Err(format!("cannot iterate over {}", type_name(&other))),
+                }
+            }
+            Ok(result)
+        }
+        Expr::Optional(target) => Ok(eval(target, input).unwrap_or_default()),
+        Expr::Pipe(a, b) => {
+            let mut result = vec![];
+            for value in eval(a, input)? {
+                result.append(&mut eval(b, &value)?);
+            }
+            Ok(result)
+        }
+        Expr::Comma(a, b) => {
+            let mut result = eval(a, input)?;
+            result.append(&mut eval(b, input)?);
+            Ok(result)
+        }
+        Expr::Binary(a, op, b) => {
+            let mut result = vec![];
+            for r in eval(b, input)? {
+                for l in eval(a, input)? {
+                    result.push(binary(&l, *op, &r)?);
+                }
+            }
+            Ok(result)
+        }
+        Expr::Negate(target) => eval(target, input)?.iter().map(negate).collect(),
+        Expr::And(a, b) | Expr::Or(a, b) => {
+            let is_and = matches!(expr, Expr::And(..));
+            let mut result = vec![];
+            for l in eval(a, input)? {
+                if truthy(&l) != is_and {
+                    result.push(Value::Bool(!is_and));
+                    continue;
+                }
+                for r in eval(b, input)? {
+                    result.push(Value::Bool(truthy(&r)));
+                }
+            }
+            Ok(result)
+        }
+        Expr::Array(inner) => match inner {
+            Some(inner) => Ok(vec![Value::Array(eval(inner, input)?)]),
+            None => Ok(vec![Value::Array(vec![])]),
+        },
+        Expr::Object(entries) => {
+            let mut objects = vec![Map::new()];
+            for (key, value) in entries {
+                let keys = eval(key, input)?;
+                let values = eval(value, input)?;
+                let mut next = vec![];
+                for object in objects.iter() {
+                    for key in keys.iter() {
+                        let key = key.as_str().ok_or_else(|| format!("object keys must be strings, not {}", type_name(key)))?;
+                        for value in values.iter() {
+                            let mut object = object.clone();
+                            object.insert(key.to_string(), value.clone());
+                            next.push(object);
+                        }
+                    }
+                }
+                objects = next;
+            }
+            Ok(objects.into_iter().map(Value::Object).collect())
+        }
+        Expr::Call(name, args) => call(name, args, input),
+    }
+}
+
+fn index(value: &Value, key: &Value) -> Result<Value, String> {
+    match (value, key) {
+        (Value::Null, _) => Ok(Value::Null),
+        (Value::Object(object), Value::String(key)) => Ok(object.get(key).cloned().unwrap_or(Value::Null)),
+        (Value::Array(arr), Value::Number(n)) => {
+            let n = n.as_f64().unwrap_or(0.0).floor() as i64;
+            let n = if n < 0 { arr.len() as i64 + n } else { n };
+            Ok(usize::try_from(n).ok().and_then(|n| arr.get(n)).cloned().unwrap_or(Value::Null))
+        }
+        _ => Err(format!("cannot index {} with {}", type_name(value), type_name(key))),
+    }
+}
+
+fn compare(a: &Value, b: &Value) -> Ordering {
+    let rank = |value: &Value| match value {
+        Value::Null => 0,
+        Value::Bool(false) => 1,
+        Value::Bool(true) => 2,
+        Value::Number(_) => 3,
+        Value::String(_) => 4,
+        Value::Array(_) => 5,
+        Value::Object(_) => 6,
+    };
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64().partial_cmp(&b.as_f64()).unwrap_or(Ordering::Equal),
+        (Value::String(a), Value::String(b)) => a.cmp(b),
+        (Value::Array(a), Value::Array(b)) => {
+            a.iter().zip(b.iter())
+                .map(|(a, b)| compare(a, b))
+                .find(|ordering| *ordering != Ordering::Equal)
+                .unwrap_or_else(|| a.len().cmp(&b.len()))
+        }
+        (Value::Object(a), Value::Object(b)) => {
+            let keys = |object: &Map<String, Value>| {
+                let mut keys = object.keys().cloned().collect::<Vec<_>>();
+                keys.sort();
+                keys
+            };
+            keys(a).cmp(&keys(b)).then_with(|| {
+                keys(a).iter()
+                    .map(|key| compare(&a[key], &b[key]))
+                    .find(|ordering| *ordering != Ordering::Equal)
+                    .unwrap_or(Ordering::Equal)
+            })
+        }
+        _ => rank(a).cmp(&rank(b)),
+    }
+}
+
+fn negate(value: &Value) -> Result<Value, String> {
+    let Value::Number(n) = value else {
+        return Err(format!("{} cannot be negated", type_name(value)));
+    };
+    // flip the sign of the text so that literals keep their precision
+    let text = n.to_string();
+    let negated = text.strip_prefix('-').map_or_else(|| format!("-{}", text), str::to_string);
+    serde_json::from_str(negated.as_str()).map(Value::Number).map_err(|e| e.to_string())
+}
+
+fn binary(a: &Value, op: BinaryOp, b: &Value) -> Result<Value, String> {
+    let error = || format!("{} and {} cannot be combined with {:?}", type_name(a), type_name(b), op);
+    match op {
+        BinaryOp::Eq => Ok(Value::Bool(compare(a, b) == Ordering::Equal)),
+        BinaryOp::Ne => Ok(Value::Bool(compare(a, b) != Ordering::Equal)),
+        BinaryOp::Lt => Ok(Value::Bool(compare(a, b) == Ordering::Less)),
+        BinaryOp::Le => Ok(Value::Bool(compare(a, b) != Ordering::Greater)),
+        BinaryOp::Gt => Ok(Value::Bool(compare(a, b) == Ordering::Greater)),
+        BinaryOp::Ge => Ok(Value::Bool(compare(a, b) != Ordering::Less)),
+        BinaryOp::Add => match (a, b) {
+            (Value::Null, other) | (other, Value::Null) => Ok(other.clone()),
+            (Value::Number(a), Value::Number(b)) => number(a.as_f64().unwrap_or(0.0) + b.as_f64().unwrap_or(0.0)),
+            (Value::String(a), Value::String(b)) => Ok(Value::String(format!("{}{}", a, b))),
+            (Value::Array(a), Value::Array(b)) => Ok(Value::Array(a.iter().chain(b.iter()).cloned().collect())),
+            (Value::Object(a), Value::Object(b)) => {
+                let mut result = a.clone();
+                result.extend(b.clone());
+                Ok(Value::Object(result))
+            }
+            _ => Err(error()),
+        },
+        BinaryOp::Sub => match (a, b) {
+            (Value::Number(a), Value::Number(b)) => number(a.as_f64().unwrap_or(0.0) - b.as_f64().unwrap_or(0.0)),
+            (Value::Array(a), Value::Array(b)) => Ok(Value::Array(a.iter().filter(|v| !b.contains(v)).cloned().collect())),
+            _ => Err(error()),
+        },
+        BinaryOp::Mul | BinaryOp::Div => match (a, b) {
+            (Value::Number(x), Value::Number(y)) => {
+                let (x, y) = (x.as_f64().unwrap_or(0.0), y.as_f64().unwrap_or(0.0));
+                if op == BinaryOp::Mul {
+                    number(x * y)
+                } else if y == 0.0 {
+                    Err(format!("{} and {} cannot be divided because the divisor is zero", x, y))
+                } else {
+                    number(x / y)
+                }
+            }
+            _ => Err(error()),
+        },
+    }
+}
+
+fn call(name: &str, args: &[Expr], input: &Value) -> Result<Vec<Value>, String> {
+    match (name, args) {
+        ("empty", []) => Ok(vec![]),
+        ("not", []) => Ok(vec![Value::Bool(!truthy(input))]),
+        ("type", []) => Ok(vec![Value::String(type_name(input).to_string())]),
+        ("length", []) => match input {
+            Value::Null => Ok(vec![Value::from(0)]),
+            Value::Bool(_) => Err("boolean has no length".to_string()),
+            Value::Number(n) => number(n.as_f64().unwrap_or(0.0).abs()).map(|n| vec![n]),
+            Value::String(s) => Ok(vec![Value::from(s.chars().count())]),
+            Value::Array(arr) => Ok(vec![Value::from(arr.len())]),
+            Value::Object(object) => Ok(vec![Value::from(object.len())]),
+        },
+        ("keys", []) => match input {
+            Value::Object(object) => {
+                let mut keys = object.keys().cloned().collect::<Vec<_>>();
+                keys.sort();
+                Ok(vec![Value::Array(keys.into_iter().map(Value::String).collect())])
+            }
+            Value::Array(arr) => Ok(vec![Value::Array((0..arr.len()).map(Value::from).collect())]),
+            other => Err(format!("{} has no keys", type_name(other))),
+        },
+        ("add", []) => match input {
+            Value::Array(arr) => {
+                let mut result = Value::Null;
+                for value in arr {
+                    result = binary(&result, BinaryOp::Add, value)?;
+                }
+                Ok(vec![result])
+            }
+            other => Err(format!("cannot add the elements of {}", type_name(other))),
+        },
+        ("has", [key]) => {
+            let mut result = vec![];
+            for key in eval(key, input)? {
+                result.push(Value::Bool(match (input, &key) {
+                    (Value::Object(object), Value::String(key)) => object.contains_key(key),
+                    (Value::Array(arr), Value::Number(n)) => n.as_f64().is_some_and(|n| n >= 0.0 && (n as usize) < arr.len()),
+                    _ => return Err(format!("cannot check whether {} has a {} key", type_name(input), type_name(&key))),
+                }));
+            }
+            Ok(result)
+        }
+        ("map", [f]) => eval(&Expr::Array(Some(Box::new(Expr::Pipe(Box::new(Expr::Iterate(Box::new(Expr::Identity))), Box::new(f.clone()))))), input),
+        ("select", [f]) => {
+            let mut result = vec![];
+            for condition in eval(f, input)? {
+                if truthy(&condition) {
+                    result.push(input.clone());
+                }
+            }
+            Ok(result)
+        }
+        _ => Err(format!("{}/{} is not defined", name, args.len())),
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn error(&self, message: &str) -> ViewerError {
+        let expression = self.chars.iter().collect::<String>();
+        ViewerError::parse(format!("jq {} at position {}", message, self.pos + 1), &expression, Some(1), Some(self.pos + 1))
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|c| c.is_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn starts_with(&self, s: &str) -> bool {
+        s.chars().enumerate().all(|(i, c)| self.chars.get(self.pos + i) == Some(&c))
+    }
+
+    fn eat(&mut self, s: &str) -> bool {
+        self.skip_whitespace();
+        if self.starts_with(s) {
+            self.pos += s.chars().count();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        self.skip_whitespace();
+        let next = self.chars.get(self.pos + keyword.len()).copied();
+        if self.starts_with(keyword) && !next.is_some_and(|c| c.is_alphanumeric() || c == '_') {
+            self.pos += keyword.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, s: &str) -> Result<(), ViewerError> {
+        if self.eat(s) {
+            Ok(())
+        } else {
+            Err(self.error(format!("expected `{}`", s).as_str()))
+        }
+    }
+
+    fn pipe(&mut self) -> Result<Expr, ViewerError> {
+        let left = self.comma()?;
+        if self.eat("|") {
+            return Ok(Expr::Pipe(Box::new(left), Box::new(self.pipe()?)));
+        }
+        Ok(left)
+    }
+
+    fn comma(&mut self) -> Result<Expr, ViewerError> {
+        let mut expr = self.or()?;
+        while self.eat(",") {
+            expr = Expr::Comma(Box::new(expr), Box::new(self.or()?));
+        }
+        Ok(expr)
+    }
+
+    fn or(&mut self) -> Result<Expr, ViewerError> {
+        let mut expr = self.and()?;
+        while self.eat_keyword("or") {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, ViewerError> {
+        let mut expr = self.comparison()?;
+        while self.eat_keyword("and") {
+            expr = Expr::And(Box::new(expr), Box::new(self.comparison()?));
+        }
+        Ok(expr)
+    }
+
+    fn comparison(&mut self) -> Result<Expr, ViewerError> {
+        let left = self.additive()?;
+        let ops = [
+            ("==", BinaryOp::Eq),
+            ("!=", BinaryOp::Ne),
+            ("<=", BinaryOp::Le),
+            (">=", BinaryOp::Ge),
+            ("<", BinaryOp::Lt),
+            (">", BinaryOp::Gt),
+        ];
+        for (s, op) in ops {
+            if self.eat(s) {
+                return Ok(Expr::Binary(Box::new(left), op, Box::new(self.additive()?)));
+            }
+        }
+        Ok(left)
+    }
+
+    fn additive(&mut self) -> Result<Expr, ViewerError> {
+        let mut expr = self.multiplicative()?;
+        loop {
+            let op = if self.eat("+") {
+                BinaryOp::Add
+            } else if self.eat("-") {
+                BinaryOp::Sub
+            } else {
+                return Ok(expr);
+            };
+            expr = Expr::Binary(Box::new(expr), op, Box::new(self.multiplicative()?));
+        }
+    }
+
+    fn multiplicative(&mut self) -> Result<Expr, ViewerError> {
+        let mut expr = self.unary()?;
+        loop {
+            let op = if self.eat("*") {
+                BinaryOp::Mul
+            } else if self.eat("/") {
+                BinaryOp::Div
+            } else {
+                return Ok(expr);
+            };
+            expr = Expr::Binary(Box::new(expr), op, Box::new(self.unary()?));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr, ViewerError> {
+        if self.eat("-") {
+            return Ok(Expr::Negate(Box::new(self.unary()?)));
+        }
+        self.postfix()
+    }
+
+    fn postfix(&mut self) -> Result<Expr, ViewerError> {
+        let mut expr = self.primary()?;
+        loop {
+            self.skip_whitespace();
+            if self.starts_with(".") && !self.starts_with("..") {
+                self.pos += 1;
+                expr = self.path_suffix(expr)?;
+            } else if self.peek() == Some('[') {
+                expr = self.bracket(expr)?;
+            } else if self.eat("?") {
+                expr = Expr::Optional(Box::new(expr));
+            } else {
+                return Ok(expr);
+            }
+        }
+    }
+
+    // Parses what follows a `.`: a member name, a quoted member name or a bracket.
+    fn path_suffix(&mut self, target: Expr) -> Result<Expr, ViewerError> {
+        match self.peek() {
+            Some('"') => Ok(Expr::Field(Box::new(target), self.string()?)),
+            Some('[') => self.bracket(target),
+            Some(c) if c.is_alphabetic() || c == '_' => Ok(Expr::Field(Box::new(target), self.identifier())),
+            _ => Err(self.error("expected a member name")),
+        }
+    }
+
+    fn bracket(&mut self, target: Expr) -> Result<Expr, ViewerError> {
+        self.expect("[")?;
+        if self.eat("]") {
+            return Ok(Expr::Iterate(Box::new(target)));
+        }
+        let key = self.pipe()?;
+        self.expect("]")?;
+        Ok(Expr::Index(Box::new(target), Box::new(key)))
+    }
+
+    fn identifier(&mut self) -> String {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_alphanumeric() || c == '_') {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos].iter().collect()
+    }
+
+    fn string(&mut self) -> Result<String, ViewerError> {
+        let start = self.pos;
+        self.pos += 1;
+        let mut escaped = false;
+        while let Some(c) = self.peek() {
+            self.pos += 1;
+            match c {
+                '\\' if !escaped => escaped = true,
+                '"' if !escaped => {
+                    let text = self.chars[start..self.pos].iter().collect::<String>();
+                    return serde_json::from_str(text.as_str()).map_err(|_| self.error("invalid string"));
+                }
+                _ => escaped = false,
+            }
+        }
+        Err(self.error("unterminated string"))
+    }
+
+    fn primary(&mut self) -> Result<Expr, ViewerError> {
+        self.skip_whitespace();
+        match self.peek() {
+            None => Err(self.error("unexpected end of expression")),
+            Some('.') if self.starts_with("..") => {
+                self.pos += 2;
+                Ok(Expr::RecurseAll)
+            }
+            Some('.') => {
+                self.pos += 1;
+                match self.peek() {
+                    Some(c) if c == '"' || c == '[' || c.is_alphabetic() || c == '_' => self.path_suffix(Expr::Identity),
+                    _ => Ok(Expr::Identity),
+                }
+            }
+            Some('"') => Ok(Expr::Literal(Value::String(self.string()?))),
+            Some('(') => {
+                self.pos += 1;
+                let expr = self.pipe()?;
+                self.expect(")")?;
+                Ok(expr)
+            }
+            Some('[') => {
+                self.pos += 1;
+                if self.eat("]") {
+                    return Ok(Expr::Array(None));
+                }
+                let expr = self.pipe()?;
+                self.expect("]")?;
+                Ok(Expr::Array(Some(Box::new(expr))))
+            }
+            Some('{') => {
+                self.pos += 1;
+                self.object()
+            }
+            Some(c) if c.is_ascii_digit() => {
+                let start = self.pos;
+                self.pos += 1;
+                while let Some(c) = self.peek() {
+                    // a sign only belongs to the number as the sign of its exponent
+                    let exponent_sign = matches!(c, '+' | '-') && matches!(self.chars[self.pos - 1], 'e' | 'E');
+                    if !(c.is_ascii_digit() || matches!(c, '.' | 'e' | 'E') || exponent_sign) {
+                        break;
+                    }
+                    self.pos += 1;
+                }
+                let text = self.chars[start..self.pos].iter().collect::<String>();
+                serde_json::from_str::<Number>(text.as_str())
+                    .map(|n| Expr::Literal(Value::Number(n)))
+                    .map_err(|_| self.error("invalid number"))
+            }
+            Some(c) if c.is_alphabetic() || c == '_' => {
+                let name = self.identifier();
+                match name.as_str() {
+                    "true" => return Ok(Expr::Literal(Value::Bool(true))),
+                    "false" => return Ok(Expr::Literal(Value::Bool(false))),
+                    "null" => return Ok(Expr::Literal(Value::Null)),
+                    _ => {}
+                }
+                let mut args = vec![];
+                if self.eat("(") {
+                    loop {
+                        args.push(self.pipe()?);
+                        if self.eat(")") {
+                            break;
+                        }
+                        self.expect(";")?;
+                    }
+                }
+                Ok(Expr::Call(name, args))
+            }
+            Some(_) => Err(self.error("unexpected character")),
+        }
+    }
+
+    fn object(&mut self) -> Result<Expr, ViewerError> {
+        let mut entries = vec![];
+        if self.eat("}") {
+            return Ok(Expr::Object(entries));
+        }
+        loop {
+            self.skip_whitespace();
+            let (key, shorthand) = match self.peek() {
+                Some('"') => {
+                    let key = self.string()?;
+                    (Expr::Literal(Value::String(key.clone())), Some(key))
+                }
+                Some('(') => {
+                    self.pos += 1;
+                    let key = self.pipe()?;
+                    self.expect(")")?;
+                    (key, None)
+                }
+                Some(c) if c.is_alphabetic() || c == '_' => {
+                    let key = self.identifier();
+                    (Expr::Literal(Value::String(key.clone())), Some(key))
+                }
+                _ => return Err(self.error("expected an object key")),
+            };
+            let value = if self.eat(":") {
+                self.or()?
+            } else if let Some(key) = shorthand {
+                Expr::Field(Box::new(Expr::Identity), key)
+            } else {
+                return Err(self.error("expected `:`"));
+            };
+            entries.push((key, value));
+            if self.eat("}") {
+                return Ok(Expr::Object(entries));
+            }
+            self.expect(",")?;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use super::*;
+
+    fn run(expression: &str, input: Value) -> Vec<Value> {
+        JqFilter::parse(expression).unwrap().run(&input).unwrap()
+    }
+
+    fn run_error(expression: &str, input: Value) -> bool {
+        JqFilter::parse(expression).unwrap().run(&input).is_err()
+    }
+
+    #[test]
+    fn paths_and_pipes() {
+        let input = json!({"a": [{"b": 1}, {"b": 2}], "c": null});
+        assert_eq!(run(".a[].b", input.clone()), vec![json!(1), json!(2)]);
+        assert_eq!(run(".a | length", input.clone()), vec![json!(2)]);
+        assert_eq!(run(".[\"c\"], .a[1]", input.clone()), vec![json!(null), json!({"b": 2})]);
+        assert_eq!(run(".x?.y", input), vec![json!(null)]);
+    }
+
+    #[test]
+    fn arithmetic_and_unary_minus() {
+        let input = json!({"a": 3});
+        assert_eq!(run("1-1", Value::Null), vec![json!(0)]);
+        assert_eq!(run(".a*2-1", input.clone()), vec![json!(5)]);
+        assert_eq!(run("-.a", input.clone()), vec![json!(-3)]);
+        assert_eq!(run(".a - -1", input), vec![json!(4)]);
+        assert_eq!(run("1e-2 + 2E+1", Value::Null), vec![json!(20.01)]);
+        assert_eq!(run("-12345678901234567890", Value::Null)[0].to_string(), "-12345678901234567890");
+        assert!(JqFilter::parse("-").is_err());
+        assert!(JqFilter::parse("-\"a\"").unwrap().run(&Value::Null).is_err());
+    }
+
+    #[test]
+    fn builtins_and_construction() {
+        let input = json!([{"n": "x", "v": 1}, {"n": "y", "v": 5}]);
+        assert_eq!(run("map(select(.v > 2) | .n)", input.clone()), vec![json!(["y"])]);
+        assert_eq!(run("[.[].v] | add", input.clone()), vec![json!(6)]);
+        assert_eq!(run(".[0] | {n, w: .v}", input.clone()), vec![json!({"n": "x", "w": 1})]);
+        assert_eq!(run(".[0] | keys", input), vec![json!(["n", "v"])]);
+        assert_eq!(run("true and (null or 1)", Value::Null), vec![json!(true)]);
+    }
+
+    #[test]
+    fn errors() {
+        assert!(JqFilter::parse(".a |").is_err());
+        assert!(JqFilter::parse("[1").is_err());
+        assert!(JqFilter::parse(".a.").is_err());
+        assert!(run_error(".[]", json!(1)));
+        assert!(run_error("1 / 0", Value::Null));
+    }
+}
@@ -0,0 +1,605 @@
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::rc::Rc;
+use serde_json::Value;
+use crate::document::{JsonPath, PathSegment};
+use crate::error::ViewerError;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum JsonPathMode {
+    /// Render only the selected values, as an array.
+    #[default]
+    Filter,
+    /// Render the whole document and dim the nodes that were not selected.
+    Dim,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Segment {
+    Child(Vec<Selector>),
+    Descendant(Vec<Selector>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Selector {
+    Name(String),
+    Wildcard,
+    Index(i64),
+    Slice(Option<i64>, Option<i64>, Option<i64>),
+    Filter(Box<Expr>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Expr {
+    Or(Box<Expr>, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Compare(Operand, CompareOp, Operand),
+    Exists(Operand),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Operand {
+    Literal(Value),
+    Relative(Vec<Segment>),
+    Absolute(Vec<Segment>),
+}
+
+/// A parsed JSONPath expression, e.g. `$.orders[*].items[?(@.qty > 5)]`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct JsonPathQuery {
+    segments: Vec<Segment>,
+}
+
+impl JsonPathQuery {
+    pub fn parse(expression: &str) -> Result<Self, ViewerError> {
+        let mut parser = Parser { chars: expression.chars().collect(), pos: 0 };
+        parser.skip_whitespace();
+        if !parser.eat('$') {
+            return Err(parser.error("expected `$` at the start of the expression"));
+        }
+        let segments = parser.segments()?;
+        parser.skip_whitespace();
+        if parser.pos < parser.chars.len() {
+            return Err(parser.error("unexpected character"));
+        }
+        Ok(Self { segments })
+    }
+
+    /// Returns the path and value of every node selected from `root`, in document order of evaluation.
+    pub fn select<'a>(&self, root: &'a Value) -> Vec<(JsonPath, &'a Value)> {
+        select(&self.segments, root, JsonPath::root(), root)
+    }
+}
+
+/// The nodes selected by a query, indexed by JSON Pointer for lookup while rendering.
+pub struct JsonPathState {
+    pub values: Vec<Value>,
+    matched: HashSet<String>,
+    ancestors: HashSet<String>,
+}
+
+impl JsonPathState {
+    pub fn new(value: &Value, expression: &str) -> Result<Self, ViewerError> {
+        let nodes = JsonPathQuery::parse(expression)?.select(value);
+        let mut matched = HashSet::new();
+        let mut ancestors = HashSet::new();
+        for (path, _) in nodes.iter() {
+            matched.insert(path.to_string());
+            let mut parent = path.parent();
+            while let Some(path) = parent {
+                if !ancestors.insert(path.to_string()) {
+                    break;
+                }
+                parent = path.parent();
+            }
+        }
+        let values = nodes.into_iter().map(|(_, value)| value.clone()).collect();
+        Ok(Self { values, matched, ancestors })
+    }
+
+    pub fn is_ancestor(&self, pointer: &str) -> bool {
+        self.ancestors.contains(pointer)
+    }
+
+    pub fn ancestors(&self) -> impl Iterator<Item = &String> {
+        self.ancestors.iter()
+    }
+
+    pub fn is_matched(&self, pointer: &str) -> bool {
+        self.matched.contains(pointer)
+    }
+
+    /// Whether the node at `path` was selected or lies inside a selected node.
+    pub fn covers(&self, path: &JsonPath) -> bool {
+        let mut current = Some(path);
+        while let Some(path) = current {
+            if self.matched.contains(&path.to_string()) {
+                return true;
+            }
+            current = path.parent();
+        }
+        false
+    }
+
+    /// Whether the node at `path` should be dimmed in `JsonPathMode::Dim`.
+    pub fn is_dimmed(&self, path: &JsonPath) -> bool {
+        !self.is_ancestor(&path.to_string()) && !self.covers(path)
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct JsonPathContext(pub Option<Rc<JsonPathState>>);
+
+impl PartialEq for JsonPathContext {
+    fn eq(&self, other: &Self) -> bool {
+        match (&self.0, &other.0) {
+            (Some(a), Some(b)) => Rc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+}
+
+impl JsonPathContext {
+    pub fn state(&self) -> Option<&JsonPathState> {
+        self.0.as_deref()
+    }
+}
+
+fn select<'a>(segments: &[Segment], root: &'a Value, path: JsonPath, value: &'a Value) -> Vec<(JsonPath, &'a Value)> {
+    let mut nodes = vec![(path, value)];
+    for segment in segments {
+        let mut next = vec![];
+        for (path, value) in nodes {
+            match segment {
+                Segment::Child(selectors) => apply(selectors, root, &path, value, &mut next),
+                Segment::Descendant(selectors) => {
+                    let mut stack = vec![(path, value)];
+                    while let Some((path, value)) = stack.pop() {
+                        apply(selectors, root, &path, value, &mut next);
+                        let mut children = children(&path, value);
+                        children.reverse();
+                        stack.append(&mut children);
+                    }
+                }
+            }
+        }
+        nodes = next;
+    }
+    nodes
+}
+
+fn children<'a>(path: &JsonPath, value: &'a Value) -> Vec<(JsonPath, &'a Value)> {
+    match value {
+        Value::Array(arr) => arr.iter().enumerate()
+            .map(|(index, item)| (path.child(PathSegment::Index(index)), item))
+            .collect(),
+        Value::Object(object) => object.iter()
+            .map(|(key, item)| (path.child(PathSegment::Key(key.clone())), item))
+            .collect(),
+        _ => vec![],
+    }
+}
+
+fn apply<'a>(selectors: &[Selector], root: &'a Value, path: &JsonPath, value: &'a Value, result: &mut Vec<(JsonPath, &'a Value)>) {
+    for selector in selectors {
+        match (selector, value) {
+            (Selector::Name(name), Value::Object(object)) => {
+                if let Some(item) = object.get(name) {
+                    result.push((path.child(PathSegment::Key(name.clone())), item));
+                }
+            }
+            (Selector::Wildcard, _) => result.append(&mut children(path, value)),
+            (Selector::Index(index), Value::Array(arr)) => {
+                let index = if *index < 0 { arr.len() as i64 + index } else { *index };
+                if index >= 0 && (index as usize) < arr.len() {
+                    result.push((path.child(PathSegment::Index(index as usize)), &arr[index as usize]));
+                }
+            }
+            (Selector::Slice(start, end, step), Value::Array(arr)) => {
+                for index in slice_indices(arr.len() as i64, *start, *end, step.unwrap_or(1)) {
+                    result.push((path.child(PathSegment::Index(index)), &arr[index]));
+                }
+            }
+            (Selector::Filter(expr), _) => {
+                for (path, item) in children(path, value) {
+                    if expr.test(root, item) {
+                        result.push((path, item));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn slice_indices(length: i64, start: Option<i64>, end: Option<i64>, step: i64) -> Vec<usize> {
+    let normalize = |index: i64| if index < 0 { length + index } else { index };
+    let mut result = vec![];
+    if step > 0 {
+        let start = normalize(start.unwrap_or(0)).clamp(0, length);
+        let end = normalize(end.unwrap_or(length)).clamp(0, length);
+        let mut index = start;
+        while index < end {
+            result.push(index as usize);
+            index += step;
+        }
+    } else if step < 0 {
+        let start = normalize(start.unwrap_or(length - 1)).clamp(-1, length - 1);
+        let end = end.map(normalize).unwrap_or(-length - 1).clamp(-1, length - 1);
+        let mut index = start;
+        while index > end {
+            result.push(index as usize);
+            index += step;
+        }
+    }
+    result
+}
+
+impl Expr {
+    fn test(&self, root: &Value, current: &Value) -> bool {
+        match self {
+            Expr::Or(a, b) => a.test(root, current) || b.test(root, current),
+            Expr::And(a, b) => a.test(root, current) && b.test(root, current),
+            Expr::Not(a) => !a.test(root, current),
+            Expr::Exists(operand) => match operand {
+                Operand::Literal(value) => !matches!(value, Value::Null | Value::Bool(false)),
+                _ => operand.value(root, current).is_some(),
+            },
+            Expr::Compare(a, op, b) => compare(a.value(root, current), *op, b.value(root, current)),
+        }
+    }
+}
+
+impl Operand {
+    fn value<'a>(&'a self, root: &'a Value, current: &'a Value) -> Option<&'a Value> {
+        let (segments, start) = match self {
+            Operand::Literal(value) => return Some(value),
+            Operand::Relative(segments) => (segments, current),
+            Operand::Absolute(segments) => (segments, root),
+        };
+        select(segments, root, JsonPath::root(), start).into_iter().next().map(|(_, value)| value)
+    }
+}
+
+fn compare(a: Option<&Value>, op: CompareOp, b: Option<&Value>) -> bool {
+    let ordering = match (a, b) {
+        (None, None) => Some(Ordering::Equal),
+        (Some(Value::Number(a)), Some(Value::Number(b))) => a.as_f64().partial_cmp(&b.as_f64()),
+        (Some(Value::String(a)), Some(Value::String(b))) => Some(a.cmp(b)),
+        (Some(a), Some(b)) if a == b => Some(Ordering::Equal),
+        _ => None,
+    };
+    match op {
+        CompareOp::Eq => ordering == Some(Ordering::Equal),
+        CompareOp::Ne => ordering != Some(Ordering::Equal),
+        CompareOp::Lt => ordering == Some(Ordering::Less),
+        CompareOp::Le => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+        CompareOp::Gt => ordering == Some(Ordering::Greater),
+        CompareOp::Ge => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn error(&self, message: &str) -> ViewerError {
+        let expression = self.chars.iter().collect::<String>();
+        ViewerError::parse(format!("JSONPath {} at position {}", message, self.pos + 1), &expression, Some(1), Some(self.pos + 1))
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn starts_with(&self, s: &str) -> bool {
+        s.chars().enumerate().all(|(i, c)| self.chars.get(self.pos + i) == Some(&c))
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn eat_str(&mut self, s: &str) -> bool {
+        if self.starts_with(s) {
+            self.pos += s.chars().count();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|c| c.is_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), ViewerError> {
+        self.skip_whitespace();
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.error(format!("expected `{}`", c).as_str()))
+        }
+    }
+
+    fn segments(&mut self) -> Result<Vec<Segment>, ViewerError> {
+        let mut segments = vec![];
+        loop {
+            if self.eat_str("..") {
+                segments.push(Segment::Descendant(self.dot_selectors()?));
+            } else if self.eat('.') {
+                segments.push(Segment::Child(self.dot_selectors()?));
+            } else if self.peek() == Some('[') {
+                segments.push(Segment::Child(self.bracket_selectors()?));
+            } else {
+                return Ok(segments);
+            }
+        }
+    }
+
+    fn dot_selectors(&mut self) -> Result<Vec<Selector>, ViewerError> {
+        if self.peek() == Some('[') {
+            return self.bracket_selectors();
+        }
+        if self.eat('*') {
+            return Ok(vec![Selector::Wildcard]);
+        }
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '-' || c == '$') {
+            self.pos += 1;
+        }
+        if start == self.pos {
+            return Err(self.error("expected a member name"));
+        }
+        Ok(vec![Selector::Name(self.chars[start..self.pos].iter().collect())])
+    }
+
+    fn bracket_selectors(&mut self) -> Result<Vec<Selector>, ViewerError> {
+        self.expect('[')?;
+        let mut selectors = vec![];
+        loop {
+            self.skip_whitespace();
+            selectors.push(self.bracket_selector()?);
+            self.skip_whitespace();
+            if self.eat(']') {
+                return Ok(selectors);
+            }
+            self.expect(',')?;
+        }
+    }
+
+    fn bracket_selector(&mut self) -> Result<Selector, ViewerError> {
+        match self.peek() {
+            Some('*') => {
+                self.pos += 1;
+                Ok(Selector::Wildcard)
+            }
+            Some('\'') | Some('"') => Ok(Selector::Name(self.string()?)),
+            Some('?') => {
+                self.pos += 1;
+                Ok(Selector::Filter(Box::new(self.or()?)))
+            }
+            _ => {
+                let start = self.integer()?;
+                self.skip_whitespace();
+                if !self.eat(':') {
+                    return start.map(Selector::Index).ok_or_else(|| self.error("expected a selector"));
+                }
+                self.skip_whitespace();
+                let end = self.integer()?;
+                self.skip_whitespace();
+                let step = if self.eat(':') {
+                    self.skip_whitespace();
+                    self.integer()?
+                } else {
+                    None
+                };
+                Ok(Selector::Slice(start, end, step))
+            }
+        }
+    }
+
+    fn integer(&mut self) -> Result<Option<i64>, ViewerError> {
+        let start = self.pos;
+        self.eat('-');
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        if start == self.pos {
+            return Ok(None);
+        }
+        let text = self.chars[start..self.pos].iter().collect::<String>();
+        text.parse().map(Some).map_err(|_| self.error("invalid integer"))
+    }
+
+    fn string(&mut self) -> Result<String, ViewerError> {
+        let quote = self.peek().unwrap();
+        self.pos += 1;
+        let mut result = String::new();
+        loop {
+            match self.peek() {
+                None => return Err(self.error("unterminated string")),
+                Some(c) if c == quote => {
+                    self.pos += 1;
+                    return Ok(result);
+                }
+                Some('\\') => {
+                    self.pos += 1;
+                    match self.peek() {
+                        Some('n') => result.push('\n'),
+                        Some('t') => result.push('\t'),
+                        Some('r') => result.push('\r'),
+                        Some(c) => result.push(c),
+                        None => return Err(self.error("unterminated string")),
+                    }
+                    self.pos += 1;
+                }
+                Some(c) => {
+                    result.push(c);
+                    self.pos += 1;
+                }
+            }
+        }
+    }
+
+    fn or(&mut self) -> Result<Expr, ViewerError> {
+        let mut expr = self.and()?;
+        loop {
+            self.skip_whitespace();
+            if !self.eat_str("||") {
+                return Ok(expr);
+            }
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+    }
+
+    fn and(&mut self) -> Result<Expr, ViewerError> {
+        let mut expr = self.unary()?;
+        loop {
+            self.skip_whitespace();
+            if !self.eat_str("&&") {
+                return Ok(expr);
+            }
+            expr = Expr::And(Box::new(expr), Box::new(self.unary()?));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr, ViewerError> {
+        self.skip_whitespace();
+        if self.peek() == Some('!') && !self.starts_with("!=") {
+            self.pos += 1;
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+        if self.eat('(') {
+            let expr = self.or()?;
+            self.expect(')')?;
+            return Ok(expr);
+        }
+        let left = self.operand()?;
+        self.skip_whitespace();
+        let op = [
+            ("==", CompareOp::Eq),
+            ("!=", CompareOp::Ne),
+            ("<=", CompareOp::Le),
+            (">=", CompareOp::Ge),
+            ("<", CompareOp::Lt),
+            (">", CompareOp::Gt),
+        ].into_iter().find(|(s, _)| self.eat_str(s)).map(|(_, op)| op);
+        match op {
+            Some(op) => {
+                self.skip_whitespace();
+                Ok(Expr::Compare(left, op, self.operand()?))
+            }
+            None => Ok(Expr::Exists(left)),
+        }
+    }
+
+    fn operand(&mut self) -> Result<Operand, ViewerError> {
+        self.skip_whitespace();
+        if self.eat('@') {
+            return Ok(Operand::Relative(self.segments()?));
+        }
+        if self.eat('$') {
+            return Ok(Operand::Absolute(self.segments()?));
+        }
+        if matches!(self.peek(), Some('\'') | Some('"')) {
+            return Ok(Operand::Literal(Value::String(self.string()?)));
+        }
+        for (keyword, value) in [("true", Value::Bool(true)), ("false", Value::Bool(false)), ("null", Value::Null)] {
+            let length = keyword.len();
+            let ends = !self.chars.get(self.pos + length).is_some_and(|c| c.is_alphanumeric() || *c == '_');
+            if self.starts_with(keyword) && ends {
+                self.pos += length;
+                return Ok(Operand::Literal(value));
+            }
+        }
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E')) {
+            self.pos += 1;
+        }
+        let text = self.chars[start..self.pos].iter().collect::<String>();
+        serde_json::from_str::<serde_json::Number>(text.as_str())
+            .map(|n| Operand::Literal(Value::Number(n)))
+            .map_err(|_| {
+                self.pos = start;
+                self.error("expected `@`, `$` or a literal")
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use super::*;
+
+    fn pointers(expression: &str, root: &Value) -> Vec<String> {
+        JsonPathQuery::parse(expression).unwrap().select(root).into_iter().map(|(path, _)| path.to_string()).collect()
+    }
+
+    #[test]
+    fn selectors() {
+        let root = json!({"store": {"books": [{"price": 8}, {"price": 12}, {"price": 23}], "name": "x"}});
+        assert_eq!(pointers("$.store.name", &root), ["/store/name"]);
+        assert_eq!(pointers("$.store.books[-1]", &root), ["/store/books/2"]);
+        assert_eq!(pointers("$.store.books[0:2].price", &root), ["/store/books/0/price", "/store/books/1/price"]);
+        assert_eq!(pointers("$.store.books[::-2]", &root), ["/store/books/2", "/store/books/0"]);
+        assert_eq!(pointers("$['store']['name', 'missing']", &root), ["/store/name"]);
+        assert_eq!(pointers("$..price", &root).len(), 3);
+        assert_eq!(pointers("$.store.*", &root), ["/store/books", "/store/name"]);
+    }
+
+    #[test]
+    fn filters() {
+        let root = json!([{"a": 1, "b": 2}, {"a": 3, "b": 3}, {"c": null}, {"a": "x"}]);
+        assert_eq!(pointers("$[?(@.a < @.b)]", &root), ["/0"]);
+        assert_eq!(pointers("$[?(@.a >= 3 && !(@.a == 'x'))]", &root), ["/1"]);
+        assert_eq!(pointers("$[?@.c]", &root), ["/2"]);
+        assert_eq!(pointers("$[?(@.c == null)]", &root), ["/2"]);
+        assert_eq!(pointers("$[?(@.a == 'x' || @.a == 1)]", &root), ["/0", "/3"]);
+    }
+
+    #[test]
+    fn missing_operands_compare_equal() {
+        let root = json!([{"a": 1}, {}]);
+        for expression in ["$[?(@.x == @.y)]", "$[?(@.x <= @.y)]", "$[?(@.x >= @.y)]"] {
+            assert_eq!(pointers(expression, &root), ["/0", "/1"], "{}", expression);
+        }
+        assert!(pointers("$[?(@.x < @.y)]", &root).is_empty());
+        assert_eq!(pointers("$[?(@.a != @.x)]", &root), ["/0"]);
+    }
+
+    #[test]
+    fn keywords_need_a_word_boundary() {
+        assert!(JsonPathQuery::parse("$[?(@.x == nullable)]").is_err());
+        assert!(JsonPathQuery::parse("$[?(@.x == true_)]").is_err());
+        assert!(JsonPathQuery::parse("$[?(@.x == true)]").is_ok());
+    }
+
+    #[test]
+    fn syntax_errors() {
+        for expression in ["store", "$.", "$[", "$['a'", "$[?(@.a == )]", "$.a b"] {
+            assert!(JsonPathQuery::parse(expression).is_err(), "{}", expression);
+        }
+    }
+}
@@ -1,42 +1,154 @@
 pub mod css;
+pub mod binary;
+pub mod collapse;
+pub mod error;
+pub mod format;
 pub mod common;
+pub mod convert;
 pub mod core;
+pub mod diff;
+pub mod document;
+pub mod edit;
+pub mod history;
 pub mod interaction;
+pub mod jq;
+pub mod jsonpath;
+pub mod parse;
+pub mod patch;
+pub mod persist;
+pub mod repair;
+pub mod schema;
+pub mod search;
+pub mod toolbar;
+pub mod virtual_scroll;
 
 use std::cell::RefCell;
 use std::rc::Rc;
 use serde_json::Value;
+use collapse::CollapseStore;
+use document::ValueRef;
+use edit::EditEvent;
+use history::EditHistory;
+use error::ViewerError;
+use format::Format;
+use jsonpath::JsonPathMode;
+use persist::Persist;
+use search::SearchQuery;
 use wasm_bindgen::prelude::*;
 use yew::prelude::*;
 use yew::virtual_dom::VNode;
+use yew::AppHandle;
 
 #[derive(Properties)]
 pub struct JsonViewerOption {
-    pub collapsable: bool,
-    pub root_collapsable: bool,
-    pub value_to_element: Box<dyn Fn(&Value) -> Vec<VNode>>,
     #[prop_or_default]
-    pub additional_value_to_element: Option<Box<dyn Fn(&Value) -> Vec<VNode>>>,
+    pub initial_expand_depth: Option<usize>,
+    pub value_to_element: Box<dyn Fn(&ValueRef) -> Vec<VNode>>,
+    #[prop_or_default]
+    pub additional_value_to_element: Option<Box<dyn Fn(&ValueRef) -> Vec<VNode>>>,
     #[prop_or_default]
     pub force_default_collapse_length_gte: usize,
     #[prop_or_default]
     pub dialog_index: Option<i32>,
     #[prop_or_default]
     pub use_json5: Rc<RefCell<bool>>,
+    #[prop_or_default]
+    pub page_size: usize,
+    #[prop_or_default]
+    pub virtual_scroll: bool,
+    #[prop_or(20.0)]
+    pub virtual_row_height: f64,
+    #[prop_or("80vh".to_string())]
+    pub virtual_viewport_height: String,
+    #[prop_or_default]
+    pub search_bar: bool,
+    #[prop_or_default]
+    pub search: Option<SearchQuery>,
+    #[prop_or_default]
+    pub search_index: usize,
+    #[prop_or_default]
+    pub jsonpath: Option<String>,
+    #[prop_or_default]
+    pub jsonpath_mode: JsonPathMode,
+    #[prop_or_default]
+    pub toolbar: bool,
+    #[prop_or_default]
+    pub expand_depth_override: Option<usize>,
+    #[prop_or_default]
+    pub collapse_generation: usize,
+    /// Replaces the collapse state whenever a new `Rc` is passed in.
+    #[prop_or_default]
+    pub collapse_state: Option<Rc<CollapseStore>>,
+    #[prop_or_default]
+    pub on_collapse_change: Option<Callback<CollapseStore>>,
+    /// Saves the collapse state on every change and restores it on mount.
+    #[prop_or_default]
+    pub persist: Option<Persist>,
+    /// Lets the user edit values and keys (double-click) and add and remove members and elements.
+    /// Not available with `virtual_scroll`.
+    #[prop_or_default]
+    pub editable: bool,
+    #[prop_or_default]
+    pub on_edit: Option<Callback<EditEvent>>,
+    /// Undoes and redoes the edits from outside the viewer; Ctrl+Z and Ctrl+Shift+Z work either way.
+    #[prop_or_default]
+    pub history: Option<EditHistory>,
+    #[prop_or(history::DEFAULT_LIMIT)]
+    pub history_limit: usize,
+    /// A JSON Schema to validate the value against; invalid values are marked and listed.
+    #[prop_or_default]
+    pub schema: Option<Rc<Value>>,
 }
 
 impl JsonViewerOption {
     pub fn is_force_default_collapse(&self, length: usize) -> bool {
-        self.force_default_collapse_length_gte > 0 && length >= self.force_default_collapse_length_gte
+        self.page_size == 0
+            && self.force_default_collapse_length_gte > 0
+            && length >= self.force_default_collapse_length_gte
+    }
+
+    pub fn is_default_collapsed(&self, value: &Value, depth: usize, expand: &core::ExpandState) -> bool {
+        if let Some(expand_depth) = expand.depth {
+            return depth >= expand_depth;
+        }
+        self.initial_expand_depth.is_some_and(|expand_depth| depth >= expand_depth)
+            || self.is_force_default_collapse(common::value_length(value))
     }
 }
 
 impl PartialEq for JsonViewerOption {
     fn eq(&self, other: &Self) -> bool {
-        self.collapsable == other.collapsable
-        && self.root_collapsable == other.root_collapsable
+        self.initial_expand_depth == other.initial_expand_depth
         && self.force_default_collapse_length_gte == other.force_default_collapse_length_gte
         && self.dialog_index == other.dialog_index
+        && self.page_size == other.page_size
+        && self.virtual_scroll == other.virtual_scroll
+        && self.virtual_row_height == other.virtual_row_height
+        && self.virtual_viewport_height == other.virtual_viewport_height
+        && self.search_bar == other.search_bar
+        && self.search == other.search
+        && self.search_index == other.search_index
+        && self.jsonpath == other.jsonpath
+        && self.jsonpath_mode == other.jsonpath_mode
+        && self.toolbar == other.toolbar
+        && self.expand_depth_override == other.expand_depth_override
+        && self.collapse_generation == other.collapse_generation
+        && match (&self.collapse_state, &other.collapse_state) {
+            (Some(a), Some(b)) => Rc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        }
+        && self.on_collapse_change == other.on_collapse_change
+        && self.persist == other.persist
+        && self.editable == other.editable
+        && self.on_edit == other.on_edit
+        && self.history == other.history
+        && self.history_limit == other.history_limit
+        && match (&self.schema, &other.schema) {
+            (Some(a), Some(b)) => Rc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        }
     }
 }
 
@@ -44,46 +156,90 @@ impl Default for JsonViewerOption {
     fn default() -> Self {
         let use_json5 = Rc::new(RefCell::new(false));
         Self {
-            collapsable: false,
-            root_collapsable: false,
+            initial_expand_depth: None,
             force_default_collapse_length_gte: 100,
             dialog_index: None,
             value_to_element: interaction::default_interaction(use_json5.clone()),
             additional_value_to_element: None,
             use_json5: use_json5.clone(),
+            page_size: 0,
+            virtual_scroll: false,
+            virtual_row_height: 20.0,
+            virtual_viewport_height: "80vh".to_string(),
+            search_bar: false,
+            search: None,
+            search_index: 0,
+            jsonpath: None,
+            jsonpath_mode: JsonPathMode::Filter,
+            toolbar: false,
+            expand_depth_override: None,
+            collapse_generation: 0,
+            collapse_state: None,
+            on_collapse_change: None,
+            persist: None,
+            editable: false,
+            on_edit: None,
+            history: None,
+            history_limit: history::DEFAULT_LIMIT,
+            schema: None,
         }
     }
 }
 
 pub struct JsonViewer {
-    value: Value,
+    value: ValueRef,
     option: JsonViewerOption,
 }
 
 impl JsonViewer {
-    pub fn new(value: Value) -> Self {
+    pub fn new(value: impl Into<ValueRef>) -> Self {
         Self::new_with_option(value, Default::default())
     }
 
-    pub fn new_with_option(value: Value, option: JsonViewerOption) -> Self {
-        Self { value, option }
+    pub fn new_with_option(value: impl Into<ValueRef>, option: JsonViewerOption) -> Self {
+        Self { value: value.into(), option }
     }
 
     pub fn render(self) -> Html {
         let option = Rc::new(self.option);
-        let value = Rc::new(self.value);
+        let value = self.value;
         html! {
             <core::RootRender value={value} option={option}/>
         }
     }
 }
 
-#[wasm_bindgen]
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Clone)]
 pub struct JsonViewRenderOption {
+    pub initial_expand_depth: Option<usize>,
     pub collapsable: Option<bool>,
     pub root_collapsable: Option<bool>,
     pub force_default_collapse_length_gte: Option<usize>,
     pub use_json5: Option<bool>,
+    /// `"json"`, `"json5"`, `"yaml"`, `"toml"` or `"ndjson"`; takes precedence over `use_json5`.
+    pub format: Option<String>,
+    /// Repair malformed JSON, JSON5 and NDJSON records instead of failing.
+    pub lenient: Option<bool>,
+    pub page_size: Option<usize>,
+    pub virtual_scroll: Option<bool>,
+    pub virtual_row_height: Option<f64>,
+    pub virtual_viewport_height: Option<f64>,
+    pub search_bar: Option<bool>,
+    pub jsonpath: Option<String>,
+    pub jsonpath_dim: Option<bool>,
+    pub toolbar: Option<bool>,
+    pub persist_id: Option<String>,
+    pub persist_in_hash: Option<bool>,
+    /// Pairs array elements of a diff by this field instead of by index.
+    pub diff_key: Option<String>,
+    pub editable: Option<bool>,
+    /// Called with the edited value and the JSON Patch of each edit, like the `json-edit` event.
+    pub on_edit: Option<js_sys::Function>,
+    /// How many edits can be undone, 100 by default.
+    pub history_limit: Option<usize>,
+    /// A JSON Schema, as JSON text.
+    pub schema: Option<String>,
 }
 
 #[wasm_bindgen]
@@ -91,41 +247,380 @@ impl JsonViewRenderOption {
     #[wasm_bindgen(constructor)]
     pub fn new() -> Self {
         Self {
+            initial_expand_depth: None,
             collapsable: None,
             root_collapsable: None,
             force_default_collapse_length_gte: None,
             use_json5: None,
+            format: None,
+            lenient: None,
+            page_size: None,
+            virtual_scroll: None,
+            virtual_row_height: None,
+            virtual_viewport_height: None,
+            search_bar: None,
+            jsonpath: None,
+            jsonpath_dim: None,
+            toolbar: None,
+            persist_id: None,
+            persist_in_hash: None,
+            diff_key: None,
+            editable: None,
+            on_edit: None,
+            history_limit: None,
+            schema: None,
         }
     }
 }
 
+impl JsonViewRenderOption {
+    fn parse(&self, value: &str) -> Result<ValueRef, ViewerError> {
+        let format = match &self.format {
+            Some(format) => format.parse::<Format>()?,
+            None if self.use_json5.is_some_and(|b| b) => Format::Json5,
+            None => Format::Json,
+        };
+        if self.lenient.is_some_and(|b| b) {
+            parse::parse_lenient(value, format)
+        } else {
+            parse::parse(value, format)
+        }
+    }
+
+    fn diff(&self, old: &str, new: &str) -> Result<ValueRef, ViewerError> {
+        let array_match = match &self.diff_key {
+            Some(key) => diff::ArrayMatch::Key(key.clone()),
+            None => diff::ArrayMatch::Index,
+        };
+        Ok(diff::diff(self.parse(old)?.get(), self.parse(new)?.get(), &array_match))
+    }
+
+    fn parse_schema(&self) -> Result<Option<Rc<Value>>, ViewerError> {
+        self.schema.as_deref()
+            .map(|text| serde_json::from_str(text).map(Rc::new).map_err(|e| ViewerError::from_serde_json(e, text)))
+            .transpose()
+    }
+
+    fn to_viewer_option(&self) -> JsonViewerOption {
+        let mut renderer_option = JsonViewerOption::default();
+        if self.root_collapsable.is_some_and(|b| b) {
+            renderer_option.initial_expand_depth = Some(0);
+        } else if self.collapsable.is_some_and(|b| b) {
+            renderer_option.initial_expand_depth = Some(1);
+        }
+        if let Some(initial_expand_depth) = self.initial_expand_depth {
+            renderer_option.initial_expand_depth = Some(initial_expand_depth);
+        }
+        if let Some(force_default_collapse_length_gte) = self.force_default_collapse_length_gte {
+            renderer_option.force_default_collapse_length_gte = force_default_collapse_length_gte;
+        }
+        if let Some(use_json5) = self.use_json5 {
+            *renderer_option.use_json5.borrow_mut() = use_json5;
+        }
+        if let Some(page_size) = self.page_size {
+            renderer_option.page_size = page_size;
+        }
+        if let Some(virtual_scroll) = self.virtual_scroll {
+            renderer_option.virtual_scroll = virtual_scroll;
+        }
+        if let Some(virtual_row_height) = self.virtual_row_height {
+            renderer_option.virtual_row_height = virtual_row_height;
+        }
+        if let Some(virtual_viewport_height) = self.virtual_viewport_height {
+            renderer_option.virtual_viewport_height = format!("{}px", virtual_viewport_height);
+        }
+        if let Some(search_bar) = self.search_bar {
+            renderer_option.search_bar = search_bar;
+        }
+        renderer_option.jsonpath = self.jsonpath.clone();
+        if self.jsonpath_dim.is_some_and(|b| b) {
+            renderer_option.jsonpath_mode = JsonPathMode::Dim;
+        }
+        if let Some(toolbar) = self.toolbar {
+            renderer_option.toolbar = toolbar;
+        }
+        if let Some(editable) = self.editable {
+            renderer_option.editable = editable;
+        }
+        if let Some(history_limit) = self.history_limit {
+            renderer_option.history_limit = history_limit;
+        }
+        renderer_option.persist = self.persist_id.clone().map(|id| {
+            if self.persist_in_hash.is_some_and(|b| b) { Persist::UrlHash(id) } else { Persist::LocalStorage(id) }
+        });
+        renderer_option
+    }
+}
+
 #[wasm_bindgen]
-pub fn json_view_render(id: &str, value: &str, option: JsonViewRenderOption) {
-    let value: Value = if option.use_json5.is_some_and(|b| b) {
-        json5::from_str(value).expect(format!("JSON parse error: {}", value).as_str())
-    } else {
-        serde_json::from_str(value).expect(format!("JSON parse error: {}", value).as_str())
+pub struct JsonViewHandle {
+    handle: Option<AppHandle<core::RootRender>>,
+    element: web_sys::Element,
+    /// Shared with the edit callback, which stores every edited document here.
+    value: Rc<RefCell<ValueRef>>,
+    option: JsonViewRenderOption,
+    expand_depth_override: Option<usize>,
+    collapse_generation: usize,
+    search: Option<SearchQuery>,
+    search_index: usize,
+    search_count: usize,
+    collapse_state: Option<Rc<CollapseStore>>,
+    current_collapse: Rc<RefCell<CollapseStore>>,
+    history: EditHistory,
+    /// Parsed from the option once, so that updates don't validate again.
+    schema: Option<Rc<Value>>,
+}
+
+impl JsonViewHandle {
+    fn props(&self) -> core::RenderProps {
+        let mut option = self.option.to_viewer_option();
+        option.expand_depth_override = self.expand_depth_override;
+        option.collapse_generation = self.collapse_generation;
+        option.search = self.search.clone();
+        option.search_index = self.search_index;
+        option.collapse_state = self.collapse_state.clone();
+        option.on_collapse_change = Some(Callback::from({
+            let current_collapse = self.current_collapse.clone();
+            move |store: CollapseStore| *current_collapse.borrow_mut() = store
+        }));
+        option.on_edit = Some(Callback::from({
+            let value = self.value.clone();
+            let element = self.element.clone();
+            let on_edit = self.option.on_edit.clone();
+            move |event: EditEvent| {
+                *value.borrow_mut() = event.value.clone();
+                dispatch_edit(&element, on_edit.as_ref(), &event);
+            }
+        }));
+        option.history = Some(self.history.clone());
+        option.schema = self.schema.clone();
+        core::RenderProps {
+            value: self.value.borrow().clone(),
+            option: Rc::new(option),
+            father_collapsed: Default::default(),
+            onclick: Default::default(),
+            is_root: Default::default(),
+            scroll_target: Default::default(),
+        }
+    }
+
+    fn update(&mut self) {
+        let props = self.props();
+        if let Some(handle) = self.handle.as_mut() {
+            handle.update(props);
+        }
+    }
+
+    fn refresh_search(&mut self) -> Result<(), ViewerError> {
+        self.search_count = match &self.search {
+            Some(query) => search::search(self.value.borrow().get(), query)?.len(),
+            None => 0,
+        };
+        if self.search_index >= self.search_count {
+            self.search_index = 0;
+        }
+        Ok(())
+    }
+
+    fn step_search(&mut self, delta: usize) -> Option<usize> {
+        if self.search_count == 0 {
+            return None;
+        }
+        self.search_index = (self.search_index + delta) % self.search_count;
+        self.update();
+        Some(self.search_index)
+    }
+
+    fn set_expand_depth(&mut self, depth: usize) {
+        self.expand_depth_override = Some(depth);
+        self.collapse_generation += 1;
+        self.update();
+    }
+}
+
+#[wasm_bindgen]
+impl JsonViewHandle {
+    pub fn set_value(&mut self, value: &str) -> Result<(), JsValue> {
+        *self.value.borrow_mut() = self.option.parse(value)?;
+        self.refresh_search()?;
+        self.update();
+        Ok(())
+    }
+
+    pub fn set_js_value(&mut self, value: JsValue) -> Result<(), JsValue> {
+        *self.value.borrow_mut() = ValueRef::new(convert::js_to_value(&value)?);
+        self.refresh_search()?;
+        self.update();
+        Ok(())
+    }
+
+    /// Replaces the value with the differences between two documents.
+    pub fn set_diff(&mut self, old: &str, new: &str) -> Result<(), JsValue> {
+        *self.value.borrow_mut() = self.option.diff(old, new)?;
+        self.refresh_search()?;
+        self.update();
+        Ok(())
+    }
+
+    /// Replaces the value with a CBOR (`"cbor"`) or MessagePack (`"msgpack"`) document.
+    pub fn set_bytes(&mut self, bytes: &[u8], format: &str) -> Result<(), JsValue> {
+        *self.value.borrow_mut() = binary::decode(bytes, format.parse()?)?;
+        self.refresh_search()?;
+        self.update();
+        Ok(())
+    }
+
+    pub fn set_option(&mut self, option: JsonViewRenderOption) -> Result<(), JsValue> {
+        self.schema = option.parse_schema()?;
+        self.option = option;
+        self.expand_depth_override = None;
+        self.collapse_generation += 1;
+        self.update();
+        Ok(())
+    }
+
+    pub fn expand_all(&mut self) {
+        self.set_expand_depth(usize::MAX);
+    }
+
+    pub fn collapse_all(&mut self) {
+        self.set_expand_depth(0);
+    }
+
+    pub fn expand_to_depth(&mut self, depth: usize) {
+        self.set_expand_depth(depth);
+    }
+
+    /// Returns the collapse state as a JSON object mapping JSON Pointers to `true` (collapsed) or `false` (expanded).
+    pub fn collapse_state(&self) -> String {
+        self.current_collapse.borrow().to_json()
+    }
+
+    pub fn set_collapse_state(&mut self, state: &str) -> Result<(), JsValue> {
+        self.collapse_state = Some(Rc::new(CollapseStore::from_json(state)?));
+        self.update();
+        Ok(())
+    }
+
+    pub fn set_collapsed(&mut self, pointer: &str, collapsed: bool) {
+        let mut store = self.current_collapse.borrow().clone();
+        store.set(pointer.to_string(), collapsed);
+        self.collapse_state = Some(Rc::new(store));
+        self.update();
+    }
+
+    pub fn search(&mut self, pattern: &str, regex: bool, case_sensitive: bool) -> Result<usize, JsValue> {
+        let mut query = SearchQuery::new(pattern);
+        query.regex = regex;
+        query.case_sensitive = case_sensitive;
+        self.search = Some(query);
+        self.search_index = 0;
+        self.refresh_search()?;
+        self.update();
+        Ok(self.search_count)
+    }
+
+    pub fn search_next(&mut self) -> Option<usize> {
+        self.step_search(1)
+    }
+
+    pub fn search_previous(&mut self) -> Option<usize> {
+        self.step_search(self.search_count.saturating_sub(1))
+    }
+
+    pub fn clear_search(&mut self) {
+        self.search = None;
+        self.search_index = 0;
+        self.search_count = 0;
+        self.update();
+    }
+
+    pub fn undo(&self) {
+        self.history.undo();
+    }
+
+    pub fn redo(&self) {
+        self.history.redo();
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.history.can_undo()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.history.can_redo()
+    }
+
+    pub fn destroy(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            handle.destroy();
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn json_view_render(id: &str, value: &str, option: JsonViewRenderOption) -> Result<JsonViewHandle, JsValue> {
+    let value = option.parse(value)?;
+    mount(id, value, option)
+}
+
+#[wasm_bindgen]
+pub fn json_view_render_value(id: &str, value: JsValue, option: JsonViewRenderOption) -> Result<JsonViewHandle, JsValue> {
+    let value = ValueRef::new(convert::js_to_value(&value)?);
+    mount(id, value, option)
+}
+
+/// Renders `new` with every difference from `old` marked.
+#[wasm_bindgen]
+pub fn json_diff_render(id: &str, old: &str, new: &str, option: JsonViewRenderOption) -> Result<JsonViewHandle, JsValue> {
+    let value = option.diff(old, new)?;
+    mount(id, value, option)
+}
+
+/// Renders a CBOR (`"cbor"`) or MessagePack (`"msgpack"`) document from a `Uint8Array`.
+#[wasm_bindgen]
+pub fn json_view_render_bytes(id: &str, bytes: &[u8], format: &str, option: JsonViewRenderOption) -> Result<JsonViewHandle, JsValue> {
+    let value = binary::decode(bytes, format.parse()?)?;
+    mount(id, value, option)
+}
+
+/// Passes an edit to the `on_edit` option and dispatches it as a `json-edit` event whose detail
+/// holds the `value` and the `patch`.
+fn dispatch_edit(element: &web_sys::Element, on_edit: Option<&js_sys::Function>, event: &EditEvent) {
+    let value = js_sys::JSON::parse(&event.value.get().to_string()).unwrap_or(JsValue::NULL);
+    let patch = js_sys::JSON::parse(&serde_json::to_string(&event.patch).unwrap()).unwrap_or(JsValue::NULL);
+    if let Some(on_edit) = on_edit {
+        let _ = on_edit.call2(&JsValue::NULL, &value, &patch);
+    }
+    let detail = js_sys::Object::new();
+    let _ = js_sys::Reflect::set(&detail, &JsValue::from_str("value"), &value);
+    let _ = js_sys::Reflect::set(&detail, &JsValue::from_str("patch"), &patch);
+    let init = web_sys::CustomEventInit::new();
+    init.set_detail(&detail);
+    if let Ok(custom_event) = web_sys::CustomEvent::new_with_event_init_dict("json-edit", &init) {
+        let _ = element.dispatch_event(&custom_event);
+    }
+}
+
+fn mount(id: &str, value: ValueRef, option: JsonViewRenderOption) -> Result<JsonViewHandle, JsValue> {
+    let element = gloo::utils::document().get_element_by_id(id).ok_or_else(|| ViewerError::element_not_found(id))?;
+    let schema = option.parse_schema()?;
+    let mut handle = JsonViewHandle {
+        handle: None,
+        element: element.clone(),
+        value: Rc::new(RefCell::new(value)),
+        option,
+        expand_depth_override: None,
+        collapse_generation: 0,
+        search: None,
+        search_index: 0,
+        search_count: 0,
+        collapse_state: None,
+        current_collapse: Default::default(),
+        history: Default::default(),
+        schema,
     };
-    let element = gloo::utils::document().get_element_by_id(id).expect(format!("element({}) not found", id).as_str());
-    let mut renderer_option = JsonViewerOption::default();
-    if let Some(collapsable) = option.collapsable {
-        renderer_option.collapsable = collapsable;
-    }
-    if let Some(root_collapsable) = option.root_collapsable {
-        renderer_option.root_collapsable = root_collapsable;
-    }
-    if let Some(force_default_collapse_length_gte) = option.force_default_collapse_length_gte {
-        renderer_option.force_default_collapse_length_gte = force_default_collapse_length_gte;
-    }
-    if let Some(use_json5) = option.use_json5 {
-        *renderer_option.use_json5.borrow_mut() = use_json5;
-    }
-    let renderer = yew::Renderer::<core::RootRender>::with_root_and_props(element, core::RenderProps {
-        value: Rc::new(value),
-        option: Rc::new(renderer_option),
-        father_collapsed: Default::default(),
-        onclick: Default::default(),
-        is_root: Default::default(),
-    });
-    renderer.render();
-}
\ No newline at end of file
+    let renderer = yew::Renderer::<core::RootRender>::with_root_and_props(element, handle.props());
+    handle.handle = Some(renderer.render());
+    Ok(handle)
+}
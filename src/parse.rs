@@ -0,0 +1,227 @@
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+use std::fmt;
+use serde::de::{DeserializeSeed, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde_json::{Map, Number, Value};
+use crate::document::{DuplicateKeys, PathSegment, SourceInfo, ValueRef};
+use crate::error::ViewerError;
+use crate::format::Format;
+use crate::repair;
+
+/// Key under which serde_json's `arbitrary_precision` feature hands a number's source text to `visit_map`.
+const NUMBER_TOKEN: &str = "$serde_json::private::Number";
+
+/// Parses text in `format` keeping object keys in source order and recording the values
+/// shadowed by duplicate keys, which a plain `serde_json::from_str` silently drops.
+/// JSON numbers keep their source text, so big integers and long decimals are not rounded.
+pub fn parse(text: &str, format: Format) -> Result<ValueRef, ViewerError> {
+    let duplicates = RefCell::new(DuplicateKeys::default());
+    let seed = Seed { pointer: String::new(), duplicates: &duplicates };
+    let value = match format {
+        Format::Json => parse_json(text, seed).map_err(|e| ViewerError::from_serde_json(e, text))?,
+        Format::Json5 => {
+            let mut deserializer = json5::Deserializer::from_str(text).map_err(|e| ViewerError::from_json5(e, text))?;
+            seed.deserialize(&mut deserializer).map_err(|e| ViewerError::from_json5(e, text))?
+        }
+        Format::Yaml => {
+            seed.deserialize(serde_yaml::Deserializer::from_str(text)).map_err(|e| ViewerError::from_yaml(e, text))?
+        }
+        Format::Toml => return parse_toml(text),
+        Format::Ndjson => return Ok(parse_ndjson(text, false)),
+    };
+    Ok(ValueRef::with_source(value, SourceInfo { duplicates: duplicates.into_inner(), ..Default::default() }))
+}
+
+/// Like `parse`, but malformed JSON, JSON5 and NDJSON records are repaired instead of failing.
+pub fn parse_lenient(text: &str, format: Format) -> Result<ValueRef, ViewerError> {
+    match format {
+        Format::Ndjson => Ok(parse_ndjson(text, true)),
+        Format::Json | Format::Json5 => Ok(parse(text, format).unwrap_or_else(|_| repair::parse(text))),
+        _ => parse(text, format),
+    }
+}
+
+fn parse_json(text: &str, seed: Seed) -> Result<Value, serde_json::Error> {
+    let mut deserializer = serde_json::Deserializer::from_str(text);
+    let value = seed.deserialize(&mut deserializer)?;
+    deserializer.end()?;
+    Ok(value)
+}
+
+/// Parses every non-blank line on its own; lines that fail are kept as their raw text with the error
+/// recorded in `SourceInfo::errors`, so one bad record doesn't hide the rest. In lenient mode they
+/// are repaired instead.
+fn parse_ndjson(text: &str, lenient: bool) -> ValueRef {
+    let duplicates = RefCell::new(DuplicateKeys::default());
+    let mut source = SourceInfo::default();
+    let mut records = vec![];
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let pointer = format!("/{}", records.len());
+        let seed = Seed { pointer: pointer.clone(), duplicates: &duplicates };
+        let record = parse_json(line, seed).unwrap_or_else(|e| {
+            if lenient {
+                return repair::parse_into(line, &pointer, index, &mut source);
+            }
+            source.errors.insert(pointer.clone(), e.to_string());
+            Value::String(line.to_string())
+        });
+        source.lines.insert(pointer, index + 1);
+        records.push(record);
+    }
+    source.duplicates.extend(duplicates.into_inner());
+    ValueRef::with_source(Value::Array(records), source)
+}
+
+struct Seed<'a> {
+    pointer: String,
+    duplicates: &'a RefCell<DuplicateKeys>,
+}
+
+impl<'a> Seed<'a> {
+    fn child(&self, segment: PathSegment) -> Self {
+        Seed { pointer: format!("{}/{}", self.pointer, segment), duplicates: self.duplicates }
+    }
+}
+
+impl<'de, 'a> DeserializeSeed<'de> for Seed<'a> {
+    type Value = Value;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de, 'a> Visitor<'de> for Seed<'a> {
+    type Value = Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("any valid JSON value")
+    }
+
+    fn visit_bool<E>(self, v: bool) -> Result<Value, E> {
+        Ok(Value::Bool(v))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Value, E> {
+        Ok(Value::Number(v.into()))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Value, E> {
+        Ok(Value::Number(v.into()))
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<Value, E> {
+        Ok(serde_json::Number::from_f64(v).map(Value::Number).unwrap_or(Value::Null))
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Value, E> {
+        Ok(Value::String(v.to_string()))
+    }
+
+    fn visit_string<E>(self, v: String) -> Result<Value, E> {
+        Ok(Value::String(v))
+    }
+
+    fn visit_none<E>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_unit<E>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+        let mut result = vec![];
+        while let Some(item) = seq.next_element_seed(self.child(PathSegment::Index(result.len())))? {
+            result.push(item);
+        }
+        Ok(Value::Array(result))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Value, A::Error> {
+        let mut result = Map::new();
+        while let Some(key) = map.next_key::<String>()? {
+            if key == NUMBER_TOKEN && result.is_empty() {
+                let text = map.next_value::<String>()?;
+                return serde_json::from_str::<Number>(&text).map(Value::Number).map_err(serde::de::Error::custom);
+            }
+            let child = self.child(PathSegment::Key(key.clone()));
+            let pointer = child.pointer.clone();
+            if result.contains_key(&key) {
+                // duplicates found inside the value about to be shadowed no longer point anywhere
+                self.duplicates.borrow_mut().remove_descendants(&pointer);
+            }
+            let value = map.next_value_seed(child)?;
+            if let Some(shadowed) = result.insert(key, value) {
+                self.duplicates.borrow_mut().push(pointer, shadowed);
+            }
+        }
+        Ok(Value::Object(result))
+    }
+}
+
+fn parse_toml(text: &str) -> Result<ValueRef, ViewerError> {
+    let document = text.parse::<toml_edit::Document>().map_err(|e| ViewerError::from_toml(e, text))?;
+    let mut datetimes = BTreeSet::new();
+    let value = toml_table(document.as_table().iter(), "", &mut datetimes);
+    Ok(ValueRef::with_source(value, SourceInfo { datetimes, ..Default::default() }))
+}
+
+fn toml_table<'a>(
+    entries: impl Iterator<Item = (&'a str, &'a toml_edit::Item)>,
+    pointer: &str,
+    datetimes: &mut BTreeSet<String>,
+) -> Value {
+    let mut result = Map::new();
+    for (key, item) in entries {
+        let pointer = format!("{}/{}", pointer, PathSegment::Key(key.to_string()));
+        let value = match item {
+            toml_edit::Item::None => continue,
+            toml_edit::Item::Value(value) => toml_value(value, &pointer, datetimes),
+            toml_edit::Item::Table(table) => toml_table(table.iter(), &pointer, datetimes),
+            toml_edit::Item::ArrayOfTables(tables) => Value::Array(
+                tables.iter().enumerate()
+                    .map(|(index, table)| toml_table(table.iter(), &format!("{}/{}", pointer, index), datetimes))
+                    .collect(),
+            ),
+        };
+        result.insert(key.to_string(), value);
+    }
+    Value::Object(result)
+}
+
+fn toml_value(value: &toml_edit::Value, pointer: &str, datetimes: &mut BTreeSet<String>) -> Value {
+    match value {
+        toml_edit::Value::String(s) => Value::String(s.value().clone()),
+        toml_edit::Value::Integer(n) => Value::Number((*n.value()).into()),
+        toml_edit::Value::Float(n) => Number::from_f64(*n.value())
+            .map(Value::Number)
+            .unwrap_or_else(|| Value::String(n.value().to_string())),
+        toml_edit::Value::Boolean(b) => Value::Bool(*b.value()),
+        toml_edit::Value::Datetime(datetime) => {
+            datetimes.insert(pointer.to_string());
+            Value::String(datetime.value().to_string())
+        }
+        toml_edit::Value::Array(arr) => Value::Array(
+            arr.iter().enumerate()
+                .map(|(index, value)| toml_value(value, &format!("{}/{}", pointer, index), datetimes))
+                .collect(),
+        ),
+        toml_edit::Value::InlineTable(table) => {
+            let mut result = Map::new();
+            for (key, value) in table.iter() {
+                let pointer = format!("{}/{}", pointer, PathSegment::Key(key.to_string()));
+                result.insert(key.to_string(), toml_value(value, &pointer, datetimes));
+            }
+            Value::Object(result)
+        }
+    }
+}
@@ -0,0 +1,201 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use crate::error::ViewerError;
+
+/// A JSON Patch (RFC 6902) operation, limited to the ones the editor produces.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum PatchOperation {
+    Add { path: String, value: Value },
+    Remove { path: String },
+    Replace { path: String, value: Value },
+    Move { from: String, path: String },
+}
+
+fn split_pointer(pointer: &str) -> Result<(&str, String), ViewerError> {
+    let (parent, last) = pointer.rsplit_once('/')
+        .ok_or_else(|| ViewerError::patch(format!("invalid JSON Pointer: {:?}", pointer)))?;
+    Ok((parent, last.replace("~1", "/").replace("~0", "~")))
+}
+
+fn resolve_mut<'a>(value: &'a mut Value, pointer: &str) -> Result<&'a mut Value, ViewerError> {
+    value.pointer_mut(pointer).ok_or_else(|| ViewerError::patch(format!("path not found: {}", pointer)))
+}
+
+/// Whether adding the member or element at `parent`/`token` back after removing it restores its
+/// position: always for arrays, but add appends to objects, so only for their last member.
+fn add_restores(value: &Value, parent: &str, token: &str) -> bool {
+    match value.pointer(parent) {
+        Some(Value::Object(object)) => object.keys().next_back().is_none_or(|last| last == token),
+        _ => true,
+    }
+}
+
+fn common_ancestor<'p>(a: &'p str, b: &str) -> &'p str {
+    let mut ancestor = a;
+    while !(b == ancestor || b.starts_with(&format!("{}/", ancestor))) {
+        ancestor = ancestor.rsplit_once('/').map_or("", |(parent, _)| parent);
+    }
+    ancestor
+}
+
+fn array_index(token: &str, length: usize, allow_end: bool) -> Result<usize, ViewerError> {
+    let index = match token {
+        "-" if allow_end => length,
+        _ => token.parse::<usize>().map_err(|_| ViewerError::patch(format!("invalid array index: {}", token)))?,
+    };
+    if index > length || (index == length && !allow_end) {
+        return Err(ViewerError::patch(format!("array index out of bounds: {}", index)));
+    }
+    Ok(index)
+}
+
+/// Applies `operation` to `value` and returns the operation that undoes it.
+pub fn apply(value: &mut Value, operation: &PatchOperation) -> Result<PatchOperation, ViewerError> {
+    match operation {
+        PatchOperation::Add { path, value: new } if path.is_empty() => {
+            let old = std::mem::replace(value, new.clone());
+            Ok(PatchOperation::Replace { path: String::new(), value: old })
+        }
+        PatchOperation::Add { path, value: new } => {
+            let (parent, token) = split_pointer(path)?;
+            match resolve_mut(value, parent)? {
+                Value::Array(arr) => {
+                    let index = array_index(&token, arr.len(), true)?;
+                    arr.insert(index, new.clone());
+                    Ok(PatchOperation::Remove { path: format!("{}/{}", parent, index) })
+                }
+                Value::Object(object) => match object.insert(token, new.clone()) {
+                    Some(old) => Ok(PatchOperation::Replace { path: path.clone(), value: old }),
+                    None => Ok(PatchOperation::Remove { path: path.clone() }),
+                },
+                _ => Err(ViewerError::patch(format!("cannot add to a scalar: {}", parent))),
+            }
+        }
+        PatchOperation::Remove { path } => {
+            let (parent, token) = split_pointer(path)?;
+            if !add_restores(value, parent, &token) && value.pointer(path).is_some() {
+                let container = resolve_mut(value, parent)?;
+                let old = container.clone();
+                container.as_object_mut().unwrap().shift_remove(&token);
+                return Ok(PatchOperation::Replace { path: parent.to_string(), value: old });
+            }
+            let old = match resolve_mut(value, parent)? {
+                Value::Array(arr) => {
+                    let index = array_index(&token, arr.len(), false)?;
+                    arr.remove(index)
+                }
+                Value::Object(object) => object.shift_remove(&token)
+                    .ok_or_else(|| ViewerError::patch(format!("path not found: {}", path)))?,
+                _ => return Err(ViewerError::patch(format!("path not found: {}", path))),
+            };
+            Ok(PatchOperation::Add { path: path.clone(), value: old })
+        }
+        PatchOperation::Replace { path, value: new } => {
+            let old = std::mem::replace(resolve_mut(value, path)?, new.clone());
+            Ok(PatchOperation::Replace { path: path.clone(), value: old })
+        }
+        PatchOperation::Move { from, path } if from == path => Ok(operation.clone()),
+        PatchOperation::Move { from, path } => {
+            let (from_parent, from_token) = split_pointer(from)?;
+            let (parent, token) = split_pointer(path)?;
+            // renaming a key keeps its position in the object
+            if from_parent == parent {
+                if let Value::Object(object) = resolve_mut(value, parent)? {
+                    if !object.contains_key(&from_token) {
+                        return Err(ViewerError::patch(format!("path not found: {}", from)));
+                    }
+                    if object.contains_key(&token) {
+                        return Err(ViewerError::patch(format!("key already exists: {}", path)));
+                    }
+                    *object = std::mem::take(object).into_iter()
+                        .map(|(key, value)| if key == from_token { (token.clone(), value) } else { (key, value) })
+                        .collect::<Map<_, _>>();
+                    return Ok(PatchOperation::Move { from: path.clone(), path: from.clone() });
+                }
+            }
+            if path.starts_with(&format!("{}/", from)) {
+                return Err(ViewerError::patch(format!("cannot move {} into itself", from)));
+            }
+            let moved = value.pointer(from).cloned().ok_or_else(|| ViewerError::patch(format!("path not found: {}", from)))?;
+            // moving the member back would append it, so restore the part of the document both paths are in
+            let restore = (!add_restores(value, from_parent, &from_token)).then(|| {
+                let ancestor = common_ancestor(from_parent, parent);
+                (ancestor.to_string(), value.pointer(ancestor).cloned().unwrap_or_default())
+            });
+            apply(value, &PatchOperation::Remove { path: from.clone() })?;
+            apply(value, &PatchOperation::Add { path: path.clone(), value: moved })?;
+            Ok(match restore {
+                Some((ancestor, old)) => PatchOperation::Replace { path: ancestor, value: old },
+                None => PatchOperation::Move { from: path.clone(), path: from.clone() },
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use super::*;
+
+    /// Applies `operation`, then checks that its inverse restores the document.
+    fn apply_and_undo(document: Value, operation: PatchOperation) -> Value {
+        let mut value = document.clone();
+        let inverse = apply(&mut value, &operation).unwrap();
+        let mut undone = value.clone();
+        apply(&mut undone, &inverse).unwrap();
+        assert_eq!(undone, document);
+        assert_eq!(serde_json::to_string(&undone).unwrap(), serde_json::to_string(&document).unwrap(), "key order");
+        value
+    }
+
+    #[test]
+    fn add_remove_replace() {
+        let document = json!({"a": [1, 2], "b": {"c": 3}});
+        assert_eq!(apply_and_undo(document.clone(), PatchOperation::Add { path: "/a/1".into(), value: json!(9) }), json!({"a": [1, 9, 2], "b": {"c": 3}}));
+        assert_eq!(apply_and_undo(document.clone(), PatchOperation::Add { path: "/a/-".into(), value: json!(9) }), json!({"a": [1, 2, 9], "b": {"c": 3}}));
+        assert_eq!(apply_and_undo(document.clone(), PatchOperation::Add { path: "/b/c".into(), value: json!(9) }), json!({"a": [1, 2], "b": {"c": 9}}));
+        assert_eq!(apply_and_undo(document.clone(), PatchOperation::Add { path: "".into(), value: json!(9) }), json!(9));
+        assert_eq!(apply_and_undo(document.clone(), PatchOperation::Remove { path: "/a/0".into() }), json!({"a": [2], "b": {"c": 3}}));
+        assert_eq!(apply_and_undo(document.clone(), PatchOperation::Replace { path: "/b".into(), value: json!(null) }), json!({"a": [1, 2], "b": null}));
+    }
+
+    #[test]
+    fn escaped_keys() {
+        let document = json!({"a/b": 1, "c~d": 2});
+        assert_eq!(apply_and_undo(document.clone(), PatchOperation::Remove { path: "/a~1b".into() }), json!({"c~d": 2}));
+        assert_eq!(apply_and_undo(document, PatchOperation::Replace { path: "/c~0d".into(), value: json!(3) }), json!({"a/b": 1, "c~d": 3}));
+    }
+
+    #[test]
+    fn move_renames_in_place() {
+        let document = json!({"a": 1, "b": 2, "c": 3});
+        let renamed = apply_and_undo(document, PatchOperation::Move { from: "/b".into(), path: "/x".into() });
+        assert_eq!(serde_json::to_string(&renamed).unwrap(), r#"{"a":1,"x":2,"c":3}"#);
+        let document = json!({"a": [1, 2, 3], "b": {}});
+        assert_eq!(apply_and_undo(document.clone(), PatchOperation::Move { from: "/a/0".into(), path: "/a/2".into() }), json!({"a": [2, 3, 1], "b": {}}));
+        assert_eq!(apply_and_undo(document, PatchOperation::Move { from: "/a".into(), path: "/b/a".into() }), json!({"b": {"a": [1, 2, 3]}}));
+    }
+
+    #[test]
+    fn invalid_operations() {
+        let document = json!({"a": [1], "b": 2, "c": {}});
+        let fails = |operation: PatchOperation| apply(&mut document.clone(), &operation).is_err();
+        assert!(fails(PatchOperation::Remove { path: "/x".into() }));
+        assert!(fails(PatchOperation::Remove { path: "/a/1".into() }));
+        assert!(fails(PatchOperation::Add { path: "/a/2".into(), value: json!(0) }));
+        assert!(fails(PatchOperation::Add { path: "/b/x".into(), value: json!(0) }));
+        assert!(fails(PatchOperation::Replace { path: "/a/x".into(), value: json!(0) }));
+        assert!(fails(PatchOperation::Move { from: "/b".into(), path: "/c".into() }));
+        assert!(fails(PatchOperation::Move { from: "/c".into(), path: "/c/d".into() }));
+        assert!(fails(PatchOperation::Remove { path: "a".into() }));
+    }
+
+    #[test]
+    fn serializes_as_json_patch() {
+        let operation = PatchOperation::Move { from: "/a".into(), path: "/b".into() };
+        assert_eq!(serde_json::to_value(&operation).unwrap(), json!({"op": "move", "from": "/a", "path": "/b"}));
+        let operation: PatchOperation = serde_json::from_value(json!({"op": "add", "path": "/a", "value": 1})).unwrap();
+        assert_eq!(operation, PatchOperation::Add { path: "/a".into(), value: json!(1) });
+    }
+}
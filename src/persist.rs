@@ -0,0 +1,120 @@
+use gloo::storage::{LocalStorage, Storage};
+use gloo::utils::window;
+use wasm_bindgen::JsValue;
+use crate::collapse::CollapseStore;
+
+/// Where the collapse state of a viewer is saved, keyed by a viewer id chosen by the caller.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Persist {
+    LocalStorage(String),
+    /// Stored as a `yew_json_viewer:<id>=<state>` entry of the URL fragment, next to the state of
+    /// other viewers on the page. Nothing is saved while the fragment holds something other than
+    /// `key=value` entries, e.g. an anchor or a hash router's route.
+    UrlHash(String),
+}
+
+impl Persist {
+    pub fn load(&self) -> Option<CollapseStore> {
+        match self {
+            Persist::LocalStorage(id) => LocalStorage::get::<CollapseStore>(storage_key(id)).ok(),
+            Persist::UrlHash(id) => hash_value(&location_hash(), &storage_key(id))
+                .and_then(|state| CollapseStore::from_json(&state).ok()),
+        }
+    }
+
+    pub fn save(&self, store: &CollapseStore) {
+        match self {
+            Persist::LocalStorage(id) => {
+                let _ = LocalStorage::set(storage_key(id), store);
+            }
+            Persist::UrlHash(id) => {
+                let state = (!store.is_empty()).then(|| store.to_json());
+                let Some(hash) = hash_with(&location_hash(), &storage_key(id), state) else {
+                    return;
+                };
+                let location = window().location();
+                let url = format!(
+                    "{}{}{}",
+                    location.pathname().unwrap_or_default(),
+                    location.search().unwrap_or_default(),
+                    if hash.is_empty() { String::new() } else { format!("#{}", hash) },
+                );
+                if let Ok(history) = window().history() {
+                    let _ = history.replace_state_with_url(&JsValue::NULL, "", Some(&url));
+                }
+            }
+        }
+    }
+}
+
+fn storage_key(id: &str) -> String {
+    format!("yew_json_viewer:{}", id)
+}
+
+fn location_hash() -> String {
+    window().location().hash().unwrap_or_default()
+}
+
+fn entries(hash: &str) -> impl Iterator<Item = &str> {
+    hash.trim_start_matches('#').split('&').filter(|part| !part.is_empty())
+}
+
+/// Whether `part` of the fragment is a `key=value` entry rather than e.g. an anchor or a route.
+fn is_entry(part: &str) -> bool {
+    part.split_once('=').is_some_and(|(key, _)| !key.is_empty() && !key.contains(['/', '?', '#']))
+}
+
+fn decode(text: &str) -> String {
+    url::form_urlencoded::parse(format!("={}", text).as_bytes()).next().map(|(_, value)| value.into_owned()).unwrap_or_default()
+}
+
+fn encode(text: &str) -> String {
+    url::form_urlencoded::byte_serialize(text.as_bytes()).collect()
+}
+
+/// The value of the entry `key` in the fragment `hash`.
+fn hash_value(hash: &str, key: &str) -> Option<String> {
+    entries(hash)
+        .filter_map(|part| part.split_once('='))
+        .find(|(k, _)| decode(k) == key)
+        .map(|(_, value)| decode(value))
+}
+
+/// `hash` with the entry `key` set to `value`, or removed if `value` is `None`; the other entries are
+/// kept as they are. `None` if `hash` isn't made of entries.
+fn hash_with(hash: &str, key: &str, value: Option<String>) -> Option<String> {
+    if !entries(hash).all(is_entry) {
+        return None;
+    }
+    let mut parts = entries(hash)
+        .filter(|part| part.split_once('=').is_none_or(|(k, _)| decode(k) != key))
+        .map(str::to_string)
+        .collect::<Vec<_>>();
+    if let Some(value) = value {
+        parts.push(format!("{}={}", encode(key), encode(&value)));
+    }
+    Some(parts.join("&"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entries_of_other_viewers_are_kept() {
+        let hash = hash_with("#a=1&yew_json_viewer%3Aorders=old", "yew_json_viewer:orders", Some(r#"{"/0":true}"#.into())).unwrap();
+        assert_eq!(hash, "a=1&yew_json_viewer%3Aorders=%7B%22%2F0%22%3Atrue%7D");
+        assert_eq!(hash_value(&hash, "yew_json_viewer:orders").as_deref(), Some(r#"{"/0":true}"#));
+        assert_eq!(hash_value(&hash, "a").as_deref(), Some("1"));
+        assert_eq!(hash_with(&hash, "yew_json_viewer:orders", None).as_deref(), Some("a=1"));
+        assert_eq!(hash_with("", "k", Some("v".into())).as_deref(), Some("k=v"));
+    }
+
+    #[test]
+    fn anchors_and_routes_are_not_overwritten() {
+        assert_eq!(hash_with("#/route/x", "k", Some("v".into())), None);
+        assert_eq!(hash_with("#section", "k", Some("v".into())), None);
+        assert_eq!(hash_with("#/route?a=1", "k", Some("v".into())), None);
+        assert_eq!(hash_value("#section", "k"), None);
+    }
+}
@@ -0,0 +1,444 @@
+use std::str::FromStr;
+use serde_json::{Map, Number, Value};
+use crate::document::{PathSegment, SourceInfo, ValueRef};
+
+const MAX_DEPTH: usize = 512;
+
+/// A fault fixed while parsing malformed JSON, at a 1-based source position.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Repair {
+    pub pointer: String,
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+/// Parses JSON that doesn't have to be valid: trailing or missing commas, missing closing brackets
+/// and quotes, single quotes, unquoted keys and Python literals are repaired and recorded in
+/// `SourceInfo::repairs`. Never fails; in the worst case the result is `null` with a repair.
+pub fn parse(text: &str) -> ValueRef {
+    let mut source = SourceInfo::default();
+    let value = parse_into(text, "", 0, &mut source);
+    ValueRef::with_source(value, source)
+}
+
+/// Parses `text` as the value at `pointer`, numbering lines after `first_line`.
+pub(crate) fn parse_into(text: &str, pointer: &str, first_line: usize, source: &mut SourceInfo) -> Value {
+    let line_starts = std::iter::once(0).chain(text.match_indices('\n').map(|(index, _)| index + 1)).collect();
+    let mut parser = Parser { text, offset: 0, line_starts, first_line, source };
+    let value = parser.value(pointer, 0);
+    parser.skip_whitespace(pointer);
+    if parser.offset < text.len() {
+        parser.repair(pointer, "ignored trailing content");
+    }
+    value
+}
+
+struct Parser<'a, 's> {
+    text: &'a str,
+    offset: usize,
+    line_starts: Vec<usize>,
+    first_line: usize,
+    source: &'s mut SourceInfo,
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '$' | '-' | '.' | '+')
+}
+
+fn is_value_start(c: char) -> bool {
+    is_word_char(c) || matches!(c, '{' | '[' | '"' | '\'')
+}
+
+impl<'a, 's> Parser<'a, 's> {
+    fn peek(&self) -> Option<char> {
+        self.text[self.offset..].chars().next()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.offset += c.len_utf8();
+        Some(c)
+    }
+
+    fn repair_at(&mut self, offset: usize, pointer: &str, message: impl Into<String>) {
+        let line = self.line_starts.partition_point(|start| *start <= offset);
+        let column = self.text[self.line_starts[line - 1]..offset].chars().count() + 1;
+        let repair = Repair { pointer: pointer.to_string(), line: self.first_line + line, column, message: message.into() };
+        self.source.repairs.entry(pointer.to_string()).or_default().push(repair);
+    }
+
+    fn repair(&mut self, pointer: &str, message: impl Into<String>) {
+        self.repair_at(self.offset, pointer, message);
+    }
+
+    fn skip_whitespace(&mut self, pointer: &str) {
+        loop {
+            let rest = &self.text[self.offset..];
+            let trimmed = rest.trim_start();
+            self.offset += rest.len() - trimmed.len();
+            let comment_end = if trimmed.starts_with("//") || trimmed.starts_with('#') {
+                trimmed.find('\n').unwrap_or(trimmed.len())
+            } else if let Some(comment) = trimmed.strip_prefix("/*") {
+                comment.find("*/").map_or(trimmed.len(), |end| end + 4)
+            } else {
+                return;
+            };
+            self.repair(pointer, "removed comment");
+            self.offset += comment_end;
+        }
+    }
+
+    fn value(&mut self, pointer: &str, depth: usize) -> Value {
+        self.skip_whitespace(pointer);
+        if depth > MAX_DEPTH {
+            self.repair(pointer, "nesting too deep, dropped the rest of the input");
+            self.offset = self.text.len();
+            return Value::Null;
+        }
+        match self.peek() {
+            Some('{') => self.object(pointer, depth),
+            Some('[') => self.array(pointer, depth),
+            Some(quote @ ('"' | '\'')) => Value::String(self.string(quote, pointer)),
+            Some(c) if is_word_char(c) => self.word(pointer),
+            _ => {
+                self.repair(pointer, "inserted missing value null");
+                Value::Null
+            }
+        }
+    }
+
+    fn object(&mut self, pointer: &str, depth: usize) -> Value {
+        self.next();
+        let mut result = Map::new();
+        loop {
+            self.skip_whitespace(pointer);
+            let key = match self.peek() {
+                None => {
+                    self.repair(pointer, "closed unterminated object");
+                    break;
+                }
+                Some('}') => {
+                    self.next();
+                    break;
+                }
+                Some(']') => {
+                    self.repair(pointer, "replaced ']' with '}'");
+                    self.next();
+                    break;
+                }
+                Some(',') => {
+                    self.repair(pointer, "removed extra comma");
+                    self.next();
+                    continue;
+                }
+                Some(quote @ ('"' | '\'')) => self.string(quote, pointer),
+                Some(c) if is_word_char(c) => {
+                    let start = self.offset;
+                    while self.peek().is_some_and(is_word_char) {
+                        self.next();
+                    }
+                    self.repair_at(start, pointer, "quoted unquoted key");
+                    self.text[start..self.offset].to_string()
+                }
+                Some(c) => {
+                    self.repair(pointer, format!("removed unexpected '{}'", c));
+                    self.next();
+                    continue;
+                }
+            };
+            let child = format!("{}/{}", pointer, PathSegment::Key(key.clone()));
+            self.skip_whitespace(pointer);
+            match self.peek() {
+                Some(':') => {
+                    self.next();
+                }
+                Some('=') => {
+                    self.repair(&child, "replaced '=' with ':'");
+                    self.next();
+                }
+                _ => self.repair(&child, "inserted missing ':'"),
+            }
+            let value = self.value(&child, depth + 1);
+            if let Some(shadowed) = result.insert(key, value) {
+                self.source.duplicates.push(child, shadowed);
+            }
+            self.separator(pointer, '}');
+        }
+        Value::Object(result)
+    }
+
+    fn array(&mut self, pointer: &str, depth: usize) -> Value {
+        self.next();
+        let mut result = vec![];
+        loop {
+            self.skip_whitespace(pointer);
+            match self.peek() {
+                None => {
+                    self.repair(pointer, "closed unterminated array");
+                    break;
+                }
+                Some(']') => {
+                    self.next();
+                    break;
+                }
+                Some('}') => {
+                    self.repair(pointer, "replaced '}' with ']'");
+                    self.next();
+                    break;
+                }
+                Some(',') => {
+                    self.repair(pointer, "removed extra comma");
+                    self.next();
+                    continue;
+                }
+                Some(c) if !is_value_start(c) => {
+                    self.repair(pointer, format!("removed unexpected '{}'", c));
+                    self.next();
+                    continue;
+                }
+                Some(_) => {}
+            }
+            let child = format!("{}/{}", pointer, PathSegment::Index(result.len()));
+            result.push(self.value(&child, depth + 1));
+            self.separator(pointer, ']');
+        }
+        Value::Array(result)
+    }
+
+    /// Consumes the comma after a member, if any; a comma right before `close` is a trailing comma.
+    fn separator(&mut self, pointer: &str, close: char) {
+        self.skip_whitespace(pointer);
+        match self.peek() {
+            Some(',') => {
+                let comma = self.offset;
+                self.next();
+                self.skip_whitespace(pointer);
+                if self.peek() == Some(close) {
+                    self.repair_at(comma, pointer, "removed trailing comma");
+                }
+            }
+            None | Some('}' | ']') => {}
+            Some(_) => self.repair(pointer, "inserted missing ','"),
+        }
+    }
+
+    fn string(&mut self, quote: char, pointer: &str) -> String {
+        if quote == '\'' {
+            self.repair(pointer, "replaced single quotes with double quotes");
+        }
+        self.next();
+        let mut result = String::new();
+        loop {
+            let Some(c) = self.next() else {
+                self.repair(pointer, "closed unterminated string");
+                break;
+            };
+            match c {
+                c if c == quote => break,
+                '\\' => self.escape(&mut result, pointer),
+                c if c.is_control() => {
+                    self.repair_at(self.offset - c.len_utf8(), pointer, "escaped control character in string");
+                    result.push(c);
+                }
+                c => result.push(c),
+            }
+        }
+        result
+    }
+
+    fn escape(&mut self, result: &mut String, pointer: &str) {
+        let start = self.offset - 1;
+        match self.next() {
+            Some('"') => result.push('"'),
+            Some('\\') => result.push('\\'),
+            Some('/') => result.push('/'),
+            Some('\'') => result.push('\''),
+            Some('b') => result.push('\u{8}'),
+            Some('f') => result.push('\u{c}'),
+            Some('n') => result.push('\n'),
+            Some('r') => result.push('\r'),
+            Some('t') => result.push('\t'),
+            Some('u') => match self.unicode_escape() {
+                Some(c) => result.push(c),
+                None => {
+                    self.repair_at(start, pointer, "replaced invalid \\u escape");
+                    result.push(char::REPLACEMENT_CHARACTER);
+                }
+            },
+            Some(c) => {
+                self.repair_at(start, pointer, format!("removed invalid escape '\\{}'", c));
+                result.push(c);
+            }
+            None => {}
+        }
+    }
+
+    fn hex4(&mut self) -> Option<u32> {
+        let digits = self.text.get(self.offset..self.offset + 4).filter(|digits| digits.chars().all(|c| c.is_ascii_hexdigit()))?;
+        self.offset += 4;
+        u32::from_str_radix(digits, 16).ok()
+    }
+
+    fn unicode_escape(&mut self) -> Option<char> {
+        let high = self.hex4()?;
+        if !(0xd800..0xdc00).contains(&high) {
+            return char::from_u32(high);
+        }
+        if !self.text[self.offset..].starts_with("\\u") {
+            return None;
+        }
+        self.offset += 2;
+        let low = self.hex4().filter(|low| (0xdc00..0xe000).contains(low))?;
+        char::from_u32(0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00))
+    }
+
+    fn word(&mut self, pointer: &str) -> Value {
+        let start = self.offset;
+        while self.peek().is_some_and(is_word_char) {
+            self.next();
+        }
+        let word = &self.text[start..self.offset];
+        let literal = |value: Value, message: &str| Some((value, Some(message.to_string())));
+        let replaced = match word {
+            "true" => Some((Value::Bool(true), None)),
+            "false" => Some((Value::Bool(false), None)),
+            "null" => Some((Value::Null, None)),
+            "True" => literal(Value::Bool(true), "replaced Python True with true"),
+            "False" => literal(Value::Bool(false), "replaced Python False with false"),
+            "None" => literal(Value::Null, "replaced Python None with null"),
+            "undefined" | "NaN" | "Infinity" | "-Infinity" | "+Infinity" => {
+                Some((Value::Null, Some(format!("replaced {} with null", word))))
+            }
+            _ => number(word).map(|(number, message)| (Value::Number(number), message)),
+        };
+        let (value, message) = replaced.unwrap_or_else(|| {
+            (Value::String(word.to_string()), Some("quoted bare word".to_string()))
+        });
+        if let Some(message) = message {
+            self.repair_at(start, pointer, message);
+        }
+        value
+    }
+}
+
+/// Parses a JSON number, fixing a leading `+`, a missing digit around the decimal point and leading zeros.
+fn number(word: &str) -> Option<(Number, Option<String>)> {
+    if let Ok(number) = Number::from_str(word) {
+        return Some((number, None));
+    }
+    if !word.starts_with(|c: char| c.is_ascii_digit() || matches!(c, '-' | '+' | '.')) {
+        return None;
+    }
+    let (sign, digits) = match word.strip_prefix('-') {
+        Some(digits) => ("-", digits),
+        None => ("", word.strip_prefix('+').unwrap_or(word)),
+    };
+    let (mantissa, exponent) = match digits.find(['e', 'E']) {
+        Some(index) => digits.split_at(index),
+        None => (digits, ""),
+    };
+    let (integer, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let integer = integer.trim_start_matches('0');
+    let fixed = format!(
+        "{}{}{}{}{}",
+        sign,
+        if integer.is_empty() { "0" } else { integer },
+        if fraction.is_empty() { "" } else { "." },
+        fraction,
+        exponent,
+    );
+    Number::from_str(&fixed).ok().map(|number| (number, Some(format!("replaced number {} with {}", word, fixed))))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use super::*;
+
+    /// The repaired value and its repairs as (pointer, line, column, message).
+    fn repair(text: &str) -> (Value, Vec<(String, usize, usize, String)>) {
+        let document = parse(text);
+        let repairs = document.all_repairs().into_iter()
+            .map(|repair| (repair.pointer.clone(), repair.line, repair.column, repair.message.clone()))
+            .collect();
+        (document.get().clone(), repairs)
+    }
+
+    fn messages(text: &str) -> Vec<String> {
+        repair(text).1.into_iter().map(|(_, _, _, message)| message).collect()
+    }
+
+    #[test]
+    fn valid_json_needs_no_repairs() {
+        let (value, repairs) = repair(r#"{"a": [1, 2.5, -0, "xé\n"], "b": {"c": null, "d": true}}"#);
+        assert_eq!(value, json!({"a": [1, 2.5, -0, "x\u{e9}\n"], "b": {"c": null, "d": true}}));
+        assert!(repairs.is_empty());
+    }
+
+    #[test]
+    fn commas() {
+        let (value, repairs) = repair("[1, 2,]");
+        assert_eq!(value, json!([1, 2]));
+        assert_eq!(repairs, [(String::new(), 1, 6, "removed trailing comma".to_string())]);
+        let (value, repairs) = repair("{\"a\": 1\n \"b\": 2}");
+        assert_eq!(value, json!({"a": 1, "b": 2}));
+        assert_eq!(repairs, [(String::new(), 2, 2, "inserted missing ','".to_string())]);
+        assert_eq!(repair("[1,,2]").0, json!([1, 2]));
+        assert_eq!(messages("[1,,2]"), ["removed extra comma"]);
+    }
+
+    #[test]
+    fn unclosed_brackets_and_strings() {
+        let (value, repairs) = repair(r#"{"a": [1, {"b": "text"#);
+        assert_eq!(value, json!({"a": [1, {"b": "text"}]}));
+        assert_eq!(repairs.iter().map(|(pointer, _, _, message)| (pointer.as_str(), message.as_str())).collect::<Vec<_>>(), [
+            ("", "closed unterminated object"),
+            ("/a", "closed unterminated array"),
+            ("/a/1", "closed unterminated object"),
+            ("/a/1/b", "closed unterminated string"),
+        ]);
+        assert_eq!(repair("[1}").0, json!([1]));
+        assert_eq!(messages("[1}"), ["replaced '}' with ']'"]);
+    }
+
+    #[test]
+    fn quotes_and_keys() {
+        let (value, repairs) = repair("{'a': 'it\\'s', b: 1, c = 2}");
+        assert_eq!(value, json!({"a": "it's", "b": 1, "c": 2}));
+        assert_eq!(repairs.into_iter().map(|(_, _, column, message)| (column, message)).collect::<Vec<_>>(), [
+            (2, "replaced single quotes with double quotes".to_string()),
+            (7, "replaced single quotes with double quotes".to_string()),
+            (16, "quoted unquoted key".to_string()),
+            (22, "quoted unquoted key".to_string()),
+            (24, "replaced '=' with ':'".to_string()),
+        ]);
+        assert_eq!(repair("[hello]").0, json!(["hello"]));
+        assert_eq!(messages("[hello]"), ["quoted bare word"]);
+    }
+
+    #[test]
+    fn comments() {
+        let (value, repairs) = repair("{\n  // note\n  \"a\": 1, # more\n  /* block */ \"b\": 2\n}");
+        assert_eq!(value, json!({"a": 1, "b": 2}));
+        assert_eq!(repairs.iter().map(|(_, line, column, _)| (*line, *column)).collect::<Vec<_>>(), [(2, 3), (3, 11), (4, 3)]);
+        assert!(repairs.iter().all(|(_, _, _, message)| message == "removed comment"));
+    }
+
+    #[test]
+    fn literals_and_numbers() {
+        let (value, _) = repair("[True, False, None, NaN, undefined, +1, .5, 1., 007]");
+        assert_eq!(value, json!([true, false, null, null, null, 1, 0.5, 1, 7]));
+        assert_eq!(messages("[True, +1]"), ["replaced Python True with true", "replaced number +1 with 1"]);
+    }
+
+    #[test]
+    fn hopeless_input() {
+        assert_eq!(repair("").0, Value::Null);
+        assert_eq!(messages(""), ["inserted missing value null"]);
+        assert_eq!(repair("[1] [2]").0, json!([1]));
+        assert_eq!(messages("[1] [2]"), ["ignored trailing content"]);
+        let deep = "[".repeat(MAX_DEPTH + 10);
+        assert!(messages(&deep).contains(&"nesting too deep, dropped the rest of the input".to_string()));
+    }
+}
@@ -0,0 +1,712 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::rc::Rc;
+use regex::Regex;
+use serde_json::{Map, Value};
+use crate::document::PathSegment;
+use crate::error::ViewerError;
+
+/// `$ref`s followed without descending into the instance before a schema counts as cyclic.
+const MAX_REF_DEPTH: usize = 64;
+
+/// A value that doesn't match the schema: the JSON Pointers of the value and of the failing keyword.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Violation {
+    pub pointer: String,
+    pub schema_pointer: String,
+    pub message: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Validation {
+    violations: Vec<Violation>,
+    by_pointer: BTreeMap<String, Vec<usize>>,
+}
+
+impl Validation {
+    pub fn is_valid(&self) -> bool {
+        self.violations.is_empty()
+    }
+
+    /// Every violation, in the order the document was validated.
+    pub fn violations(&self) -> &[Violation] {
+        &self.violations
+    }
+
+    pub fn is_invalid(&self, pointer: &str) -> bool {
+        self.by_pointer.contains_key(pointer)
+    }
+
+    pub fn at(&self, pointer: &str) -> Vec<&Violation> {
+        self.by_pointer.get(pointer).map(|indices| indices.iter().map(|index| &self.violations[*index]).collect()).unwrap_or_default()
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct SchemaContext(pub Option<Rc<Validation>>);
+
+impl PartialEq for SchemaContext {
+    fn eq(&self, other: &Self) -> bool {
+        match (&self.0, &other.0) {
+            (Some(a), Some(b)) => Rc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+}
+
+impl SchemaContext {
+    pub fn validation(&self) -> Option<&Validation> {
+        self.0.as_deref()
+    }
+}
+
+/// Validates `instance` against a JSON Schema (draft 2020-12). `format` is not asserted, and `$ref`
+/// only resolves within the schema: `#`, `#/pointer`, `#anchor` and the `$id`s it declares.
+/// Fails only when the schema itself is broken.
+pub fn validate(schema: &Value, instance: &Value) -> Result<Validation, ViewerError> {
+    let mut validator = Validator { root: schema, ids: HashMap::new(), anchors: HashMap::new(), regexes: HashMap::new() };
+    validator.index(schema);
+    let mut violations = vec![];
+    validator.validate(schema, "", instance, "", 0, &mut violations)?;
+    let mut by_pointer = BTreeMap::<String, Vec<usize>>::new();
+    for (index, violation) in violations.iter().enumerate() {
+        by_pointer.entry(violation.pointer.clone()).or_default().push(index);
+    }
+    Ok(Validation { violations, by_pointer })
+}
+
+/// Members and elements a schema and its subschemas applied to, for `unevaluated*`.
+#[derive(Default)]
+struct Evaluated {
+    properties: HashSet<String>,
+    /// Elements before this index.
+    items: usize,
+    indices: HashSet<usize>,
+    all_items: bool,
+}
+
+impl Evaluated {
+    fn merge(&mut self, other: Evaluated) {
+        self.properties.extend(other.properties);
+        self.items = self.items.max(other.items);
+        self.indices.extend(other.indices);
+        self.all_items |= other.all_items;
+    }
+
+    fn is_item(&self, index: usize) -> bool {
+        self.all_items || index < self.items || self.indices.contains(&index)
+    }
+}
+
+struct Validator<'s> {
+    root: &'s Value,
+    ids: HashMap<String, &'s Value>,
+    anchors: HashMap<String, &'s Value>,
+    regexes: HashMap<String, Regex>,
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if is_integer(n) => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn is_integer(n: &serde_json::Number) -> bool {
+    n.is_i64() || n.is_u64() || n.as_f64().is_some_and(|n| n.fract() == 0.0)
+}
+
+fn type_matches(name: &str, value: &Value) -> bool {
+    name == type_name(value) || (name == "number" && value.is_number())
+}
+
+/// JSON equality, under which `1` and `1.0` are the same number.
+fn equal(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => a == b || a.as_f64() == b.as_f64(),
+        (Value::Array(a), Value::Array(b)) => a.len() == b.len() && a.iter().zip(b).all(|(a, b)| equal(a, b)),
+        (Value::Object(a), Value::Object(b)) => {
+            a.len() == b.len() && a.iter().all(|(key, a)| b.get(key).is_some_and(|b| equal(a, b)))
+        }
+        (a, b) => a == b,
+    }
+}
+
+fn short(value: &Value) -> String {
+    let text = value.to_string();
+    match text.char_indices().nth(60) {
+        Some((index, _)) => format!("{}…", &text[..index]),
+        None => text,
+    }
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut result = vec![];
+    let mut index = 0;
+    while index < bytes.len() {
+        let hex = text.get(index + 1..index + 3).and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[index], hex) {
+            (b'%', Some(byte)) => {
+                result.push(byte);
+                index += 3;
+            }
+            (byte, _) => {
+                result.push(byte);
+                index += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&result).into_owned()
+}
+
+fn child(pointer: &str, segment: PathSegment) -> String {
+    format!("{}/{}", pointer, segment)
+}
+
+impl<'s> Validator<'s> {
+    fn index(&mut self, schema: &'s Value) {
+        match schema {
+            Value::Object(object) => {
+                if let Some(id) = object.get("$id").and_then(Value::as_str) {
+                    self.ids.insert(id.trim_end_matches('#').to_string(), schema);
+                }
+                for keyword in ["$anchor", "$dynamicAnchor"] {
+                    if let Some(anchor) = object.get(keyword).and_then(Value::as_str) {
+                        self.anchors.insert(anchor.to_string(), schema);
+                    }
+                }
+                // const and enum hold instances, not schemas
+                object.iter().filter(|(key, _)| !matches!(key.as_str(), "const" | "enum")).for_each(|(_, value)| self.index(value));
+            }
+            Value::Array(arr) => arr.iter().for_each(|value| self.index(value)),
+            _ => {}
+        }
+    }
+
+    fn resolve(&self, reference: &str) -> Result<&'s Value, ViewerError> {
+        let unresolved = || ViewerError::schema(format!("cannot resolve $ref {:?}", reference));
+        let (base, fragment) = reference.split_once('#').unwrap_or((reference, ""));
+        let document = if base.is_empty() { self.root } else { *self.ids.get(base).ok_or_else(unresolved)? };
+        let fragment = percent_decode(fragment);
+        if fragment.is_empty() {
+            Ok(document)
+        } else if fragment.starts_with('/') {
+            document.pointer(&fragment).ok_or_else(unresolved)
+        } else {
+            self.anchors.get(&fragment).copied().ok_or_else(unresolved)
+        }
+    }
+
+    fn regex(&mut self, pattern: &str) -> Result<&Regex, ViewerError> {
+        if !self.regexes.contains_key(pattern) {
+            let regex = Regex::new(pattern).map_err(|e| ViewerError::schema(format!("invalid pattern {:?}: {}", pattern, e)))?;
+            self.regexes.insert(pattern.to_string(), regex);
+        }
+        Ok(&self.regexes[pattern])
+    }
+
+    fn is_match(&mut self, pattern: &str, text: &str) -> Result<bool, ViewerError> {
+        Ok(self.regex(pattern)?.is_match(text))
+    }
+
+    /// Validates without reporting, e.g. a branch of `anyOf`.
+    fn check(&mut self, schema: &'s Value, schema_pointer: &str, instance: &Value, pointer: &str, refs: usize) -> Result<Option<Evaluated>, ViewerError> {
+        let mut violations = vec![];
+        let evaluated = self.validate(schema, schema_pointer, instance, pointer, refs, &mut violations)?;
+        Ok(violations.is_empty().then_some(evaluated))
+    }
+
+    /// Validates a member or element not covered by a more specific keyword, naming it when the
+    /// schema is `false`.
+    fn validate_extra(
+        &mut self,
+        schema: &'s Value,
+        schema_pointer: &str,
+        instance: &Value,
+        pointer: &str,
+        name: String,
+        violations: &mut Vec<Violation>,
+    ) -> Result<(), ViewerError> {
+        if schema == &Value::Bool(false) {
+            violations.push(Violation {
+                pointer: pointer.to_string(),
+                schema_pointer: schema_pointer.to_string(),
+                message: format!("{} is not allowed", name),
+            });
+        } else {
+            self.validate(schema, schema_pointer, instance, pointer, 0, violations)?;
+        }
+        Ok(())
+    }
+
+    fn validate(
+        &mut self,
+        schema: &'s Value,
+        schema_pointer: &str,
+        instance: &Value,
+        pointer: &str,
+        refs: usize,
+        violations: &mut Vec<Violation>,
+    ) -> Result<Evaluated, ViewerError> {
+        let mut evaluated = Evaluated::default();
+        let object = match schema {
+            Value::Bool(true) => return Ok(evaluated),
+            Value::Bool(false) => {
+                violations.push(Violation {
+                    pointer: pointer.to_string(),
+                    schema_pointer: schema_pointer.to_string(),
+                    message: "no value is allowed here".to_string(),
+                });
+                return Ok(evaluated);
+            }
+            Value::Object(object) => object,
+            _ => return Err(ViewerError::schema(format!("schema at {:?} is neither an object nor a boolean", schema_pointer))),
+        };
+        let at = |keyword: &str| child(schema_pointer, PathSegment::Key(keyword.to_string()));
+        self.assertions(object, schema_pointer, instance, pointer, violations)?;
+        for keyword in ["$ref", "$dynamicRef"] {
+            if let Some(reference) = object.get(keyword).and_then(Value::as_str) {
+                if refs >= MAX_REF_DEPTH {
+                    return Err(ViewerError::schema(format!("$ref {:?} never reaches a schema", reference)));
+                }
+                let target = self.resolve(reference)?;
+                evaluated.merge(self.validate(target, &at(keyword), instance, pointer, refs + 1, violations)?);
+            }
+        }
+        self.applicators(object, schema_pointer, instance, pointer, refs, violations, &mut evaluated)?;
+        match instance {
+            Value::Array(arr) => self.array(object, schema_pointer, arr, pointer, violations, &mut evaluated)?,
+            Value::Object(members) => self.object(object, schema_pointer, members, pointer, violations, &mut evaluated)?,
+            _ => {}
+        }
+        Ok(evaluated)
+    }
+
+    /// Keywords that check the instance itself, reported at its pointer.
+    fn assertions(
+        &mut self,
+        object: &'s Map<String, Value>,
+        schema_pointer: &str,
+        instance: &Value,
+        pointer: &str,
+        violations: &mut Vec<Violation>,
+    ) -> Result<(), ViewerError> {
+        let mut fail = |keyword: &str, message: String| violations.push(Violation {
+            pointer: pointer.to_string(),
+            schema_pointer: child(schema_pointer, PathSegment::Key(keyword.to_string())),
+            message,
+        });
+        if let Some(types) = object.get("type") {
+            let types = match types {
+                Value::String(name) => vec![name.as_str()],
+                Value::Array(names) => names.iter().filter_map(Value::as_str).collect(),
+                _ => return Err(ViewerError::schema("\"type\" must be a string or an array".to_string())),
+            };
+            if !types.iter().any(|name| type_matches(name, instance)) {
+                fail("type", format!("expected {}, got {}", types.join(" or "), type_name(instance)));
+            }
+        }
+        if let Some(values) = object.get("enum").and_then(Value::as_array) {
+            if !values.iter().any(|value| equal(value, instance)) {
+                fail("enum", format!("must be one of {}", short(&Value::Array(values.clone()))));
+            }
+        }
+        if let Some(value) = object.get("const") {
+            if !equal(value, instance) {
+                fail("const", format!("must be {}", short(value)));
+            }
+        }
+        let number = |keyword: &str| object.get(keyword).and_then(Value::as_f64);
+        if let Some(n) = instance.as_f64() {
+            if let Some(minimum) = number("minimum").filter(|minimum| n < *minimum) {
+                fail("minimum", format!("must be at least {}", minimum));
+            }
+            if let Some(maximum) = number("maximum").filter(|maximum| n > *maximum) {
+                fail("maximum", format!("must be at most {}", maximum));
+            }
+            if let Some(minimum) = number("exclusiveMinimum").filter(|minimum| n <= *minimum) {
+                fail("exclusiveMinimum", format!("must be greater than {}", minimum));
+            }
+            if let Some(maximum) = number("exclusiveMaximum").filter(|maximum| n >= *maximum) {
+                fail("exclusiveMaximum", format!("must be less than {}", maximum));
+            }
+            if let Some(divisor) = number("multipleOf").filter(|divisor| *divisor > 0.0) {
+                let quotient = n / divisor;
+                if (quotient - quotient.round()).abs() > 1e-9 * quotient.abs().max(1.0) {
+                    fail("multipleOf", format!("must be a multiple of {}", divisor));
+                }
+            }
+        }
+        let count = |keyword: &str| object.get(keyword).and_then(Value::as_u64).map(|count| count as usize);
+        let plural = |count: usize, noun: &str| format!("{} {}{}", count, noun, if count == 1 { "" } else { "s" });
+        if let Some(text) = instance.as_str() {
+            let length = text.chars().count();
+            if let Some(minimum) = count("minLength").filter(|minimum| length < *minimum) {
+                fail("minLength", format!("must be at least {} long", plural(minimum, "character")));
+            }
+            if let Some(maximum) = count("maxLength").filter(|maximum| length > *maximum) {
+                fail("maxLength", format!("must be at most {} long", plural(maximum, "character")));
+            }
+            if let Some(pattern) = object.get("pattern").and_then(Value::as_str) {
+                if !self.is_match(pattern, text)? {
+                    fail("pattern", format!("must match /{}/", pattern));
+                }
+            }
+        }
+        if let Some(arr) = instance.as_array() {
+            if let Some(minimum) = count("minItems").filter(|minimum| arr.len() < *minimum) {
+                fail("minItems", format!("must have at least {}", plural(minimum, "item")));
+            }
+            if let Some(maximum) = count("maxItems").filter(|maximum| arr.len() > *maximum) {
+                fail("maxItems", format!("must have at most {}", plural(maximum, "item")));
+            }
+            if object.get("uniqueItems") == Some(&Value::Bool(true)) {
+                let duplicate = (1..arr.len()).find_map(|j| (0..j).find(|i| equal(&arr[*i], &arr[j])).map(|i| (i, j)));
+                if let Some((i, j)) = duplicate {
+                    fail("uniqueItems", format!("items {} and {} are equal", i, j));
+                }
+            }
+        }
+        if let Some(members) = instance.as_object() {
+            if let Some(minimum) = count("minProperties").filter(|minimum| members.len() < *minimum) {
+                fail("minProperties", format!("must have at least {}", plural(minimum, "property")));
+            }
+            if let Some(maximum) = count("maxProperties").filter(|maximum| members.len() > *maximum) {
+                fail("maxProperties", format!("must have at most {}", plural(maximum, "property")));
+            }
+            for key in object.get("required").and_then(Value::as_array).into_iter().flatten().filter_map(Value::as_str) {
+                if !members.contains_key(key) {
+                    fail("required", format!("missing required property {:?}", key));
+                }
+            }
+            for (key, required) in object.get("dependentRequired").and_then(Value::as_object).into_iter().flatten() {
+                if !members.contains_key(key) {
+                    continue;
+                }
+                for dependency in required.as_array().into_iter().flatten().filter_map(Value::as_str) {
+                    if !members.contains_key(dependency) {
+                        fail("dependentRequired", format!("property {:?} requires property {:?}", key, dependency));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// `allOf`, `anyOf`, `oneOf`, `not`, `if`/`then`/`else` and `dependentSchemas`, which apply
+    /// subschemas to the instance itself.
+    #[allow(clippy::too_many_arguments)]
+    fn applicators(
+        &mut self,
+        object: &'s Map<String, Value>,
+        schema_pointer: &str,
+        instance: &Value,
+        pointer: &str,
+        refs: usize,
+        violations: &mut Vec<Violation>,
+        evaluated: &mut Evaluated,
+    ) -> Result<(), ViewerError> {
+        let at = |keyword: &str| child(schema_pointer, PathSegment::Key(keyword.to_string()));
+        let violation = |keyword: &str, message: String| Violation { pointer: pointer.to_string(), schema_pointer: at(keyword), message };
+        let branches = |keyword: &str| object.get(keyword).and_then(Value::as_array).into_iter().flatten().enumerate();
+        for (index, branch) in branches("allOf") {
+            evaluated.merge(self.validate(branch, &child(&at("allOf"), PathSegment::Index(index)), instance, pointer, refs, violations)?);
+        }
+        if object.contains_key("anyOf") {
+            let mut matched = false;
+            for (index, branch) in branches("anyOf") {
+                if let Some(branch) = self.check(branch, &child(&at("anyOf"), PathSegment::Index(index)), instance, pointer, refs)? {
+                    evaluated.merge(branch);
+                    matched = true;
+                }
+            }
+            if !matched {
+                violations.push(violation("anyOf", "must match at least one schema in anyOf".to_string()));
+            }
+        }
+        if object.contains_key("oneOf") {
+            let mut matches = vec![];
+            for (index, branch) in branches("oneOf") {
+                if let Some(branch) = self.check(branch, &child(&at("oneOf"), PathSegment::Index(index)), instance, pointer, refs)? {
+                    matches.push((index, branch));
+                }
+            }
+            match matches.len() {
+                0 => violations.push(violation("oneOf", "must match exactly one schema in oneOf, matches none".to_string())),
+                1 => evaluated.merge(matches.pop().unwrap().1),
+                _ => {
+                    let indices = matches.iter().map(|(index, _)| index.to_string()).collect::<Vec<_>>();
+                    violations.push(violation("oneOf", format!("must match exactly one schema in oneOf, matches {}", indices.join(", "))));
+                }
+            }
+        }
+        if let Some(not) = object.get("not") {
+            if self.check(not, &at("not"), instance, pointer, refs)?.is_some() {
+                violations.push(violation("not", "must not match the schema in not".to_string()));
+            }
+        }
+        if let Some(condition) = object.get("if") {
+            let (keyword, branch) = match self.check(condition, &at("if"), instance, pointer, refs)? {
+                Some(condition) => {
+                    evaluated.merge(condition);
+                    ("then", object.get("then"))
+                }
+                None => ("else", object.get("else")),
+            };
+            if let Some(branch) = branch {
+                evaluated.merge(self.validate(branch, &at(keyword), instance, pointer, refs, violations)?);
+            }
+        }
+        if let (Some(members), Some(dependencies)) = (instance.as_object(), object.get("dependentSchemas").and_then(Value::as_object)) {
+            for (key, dependency) in dependencies.iter().filter(|(key, _)| members.contains_key(*key)) {
+                let schema_pointer = child(&at("dependentSchemas"), PathSegment::Key(key.clone()));
+                evaluated.merge(self.validate(dependency, &schema_pointer, instance, pointer, refs, violations)?);
+            }
+        }
+        Ok(())
+    }
+
+    fn array(
+        &mut self,
+        object: &'s Map<String, Value>,
+        schema_pointer: &str,
+        arr: &[Value],
+        pointer: &str,
+        violations: &mut Vec<Violation>,
+        evaluated: &mut Evaluated,
+    ) -> Result<(), ViewerError> {
+        let at = |keyword: &str| child(schema_pointer, PathSegment::Key(keyword.to_string()));
+        let item = |index: usize| child(pointer, PathSegment::Index(index));
+        // an array of `items` is the pre-2020 spelling of `prefixItems`
+        let (prefix_keyword, prefix) = match (object.get("prefixItems"), object.get("items")) {
+            (Some(Value::Array(prefix)), _) => ("prefixItems", Some(prefix)),
+            (_, Some(Value::Array(prefix))) => ("items", Some(prefix)),
+            _ => ("prefixItems", None),
+        };
+        let prefix_length = prefix.map_or(0, Vec::len);
+        for (index, (schema, value)) in prefix.into_iter().flatten().zip(arr).enumerate() {
+            self.validate(schema, &child(&at(prefix_keyword), PathSegment::Index(index)), value, &item(index), 0, violations)?;
+        }
+        evaluated.items = evaluated.items.max(prefix_length.min(arr.len()));
+        let rest = match (object.get("items"), object.get("additionalItems")) {
+            (Some(items), _) if !items.is_array() => Some(("items", items)),
+            (Some(Value::Array(_)), Some(additional)) => Some(("additionalItems", additional)),
+            _ => None,
+        };
+        if let Some((keyword, schema)) = rest {
+            for (index, value) in arr.iter().enumerate().skip(prefix_length) {
+                self.validate_extra(schema, &at(keyword), value, &item(index), format!("item {}", index), violations)?;
+            }
+            evaluated.all_items = true;
+        }
+        if let Some(contains) = object.get("contains") {
+            let mut matched = vec![];
+            for (index, value) in arr.iter().enumerate() {
+                if self.check(contains, &at("contains"), value, &item(index), 0)?.is_some() {
+                    matched.push(index);
+                }
+            }
+            let count = |keyword: &str| object.get(keyword).and_then(Value::as_u64).map(|count| count as usize);
+            let minimum = count("minContains").unwrap_or(1);
+            let violation = |keyword: &str, message: String| Violation { pointer: pointer.to_string(), schema_pointer: at(keyword), message };
+            if matched.len() < minimum {
+                let keyword = if object.contains_key("minContains") { "minContains" } else { "contains" };
+                violations.push(violation(keyword, format!("must contain at least {} matching item{}, found {}", minimum, if minimum == 1 { "" } else { "s" }, matched.len())));
+            }
+            if let Some(maximum) = count("maxContains").filter(|maximum| matched.len() > *maximum) {
+                violations.push(violation("maxContains", format!("must contain at most {} matching item{}, found {}", maximum, if maximum == 1 { "" } else { "s" }, matched.len())));
+            }
+            evaluated.indices.extend(matched);
+        }
+        if let Some(unevaluated) = object.get("unevaluatedItems") {
+            for (index, value) in arr.iter().enumerate().filter(|(index, _)| !evaluated.is_item(*index)) {
+                self.validate_extra(unevaluated, &at("unevaluatedItems"), value, &item(index), format!("item {}", index), violations)?;
+            }
+            evaluated.all_items = true;
+        }
+        Ok(())
+    }
+
+    fn object(
+        &mut self,
+        object: &'s Map<String, Value>,
+        schema_pointer: &str,
+        members: &Map<String, Value>,
+        pointer: &str,
+        violations: &mut Vec<Violation>,
+        evaluated: &mut Evaluated,
+    ) -> Result<(), ViewerError> {
+        let at = |keyword: &str| child(schema_pointer, PathSegment::Key(keyword.to_string()));
+        let member = |key: &str| child(pointer, PathSegment::Key(key.to_string()));
+        let properties = object.get("properties").and_then(Value::as_object);
+        for (key, schema) in properties.into_iter().flatten() {
+            if let Some(value) = members.get(key) {
+                let schema_pointer = child(&at("properties"), PathSegment::Key(key.clone()));
+                self.validate(schema, &schema_pointer, value, &member(key), 0, violations)?;
+                evaluated.properties.insert(key.clone());
+            }
+        }
+        let patterns = object.get("patternProperties").and_then(Value::as_object);
+        let mut pattern_matched = HashSet::new();
+        for (pattern, schema) in patterns.into_iter().flatten() {
+            for (key, value) in members {
+                if self.is_match(pattern, key)? {
+                    let schema_pointer = child(&at("patternProperties"), PathSegment::Key(pattern.clone()));
+                    self.validate(schema, &schema_pointer, value, &member(key), 0, violations)?;
+                    pattern_matched.insert(key.clone());
+                }
+            }
+        }
+        if let Some(additional) = object.get("additionalProperties") {
+            let is_additional = |key: &String| !properties.is_some_and(|properties| properties.contains_key(key)) && !pattern_matched.contains(key);
+            for (key, value) in members.iter().filter(|(key, _)| is_additional(key)) {
+                self.validate_extra(additional, &at("additionalProperties"), value, &member(key), format!("property {:?}", key), violations)?;
+                evaluated.properties.insert(key.clone());
+            }
+        }
+        evaluated.properties.extend(pattern_matched);
+        if let Some(names) = object.get("propertyNames") {
+            for key in members.keys() {
+                let mut failed = vec![];
+                self.validate(names, &at("propertyNames"), &Value::String(key.clone()), &member(key), 0, &mut failed)?;
+                violations.extend(failed.into_iter().map(|violation| Violation {
+                    message: format!("property name {:?} {}", key, violation.message),
+                    ..violation
+                }));
+            }
+        }
+        if let Some(unevaluated) = object.get("unevaluatedProperties") {
+            for (key, value) in members.iter().filter(|(key, _)| !evaluated.properties.contains(*key)) {
+                self.validate_extra(unevaluated, &at("unevaluatedProperties"), value, &member(key), format!("property {:?}", key), violations)?;
+            }
+            evaluated.properties.extend(members.keys().cloned());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use super::*;
+
+    /// (pointer, schema pointer) of every violation.
+    fn violations(schema: Value, instance: Value) -> Vec<(String, String)> {
+        validate(&schema, &instance).unwrap().violations().iter()
+            .map(|violation| (violation.pointer.clone(), violation.schema_pointer.clone()))
+            .collect()
+    }
+
+    fn pairs(expected: &[(&str, &str)]) -> Vec<(String, String)> {
+        expected.iter().map(|(pointer, schema_pointer)| (pointer.to_string(), schema_pointer.to_string())).collect()
+    }
+
+    #[test]
+    fn assertions() {
+        let schema = json!({
+            "type": "object",
+            "required": ["name", "id"],
+            "properties": {
+                "name": {"type": "string", "minLength": 2, "pattern": "^[a-z]+$"},
+                "age": {"type": "integer", "minimum": 0, "exclusiveMaximum": 150},
+                "tags": {"type": "array", "uniqueItems": true, "maxItems": 2},
+                "kind": {"enum": ["a", "b"]},
+                "price": {"multipleOf": 0.01}
+            }
+        });
+        assert!(validate(&schema, &json!({"name": "ab", "id": 1, "age": 3.0, "price": 0.3})).unwrap().is_valid());
+        assert_eq!(violations(schema, json!({"name": "A", "age": 150, "tags": [1, 1, 2], "kind": "c", "price": 0.001})), pairs(&[
+            ("", "/required"),
+            ("/name", "/properties/name/minLength"),
+            ("/name", "/properties/name/pattern"),
+            ("/age", "/properties/age/exclusiveMaximum"),
+            ("/tags", "/properties/tags/maxItems"),
+            ("/tags", "/properties/tags/uniqueItems"),
+            ("/kind", "/properties/kind/enum"),
+            ("/price", "/properties/price/multipleOf"),
+        ]));
+    }
+
+    #[test]
+    fn messages() {
+        let validation = validate(&json!({"type": ["string", "null"]}), &json!(1)).unwrap();
+        assert_eq!(validation.at("")[0].message, "expected string or null, got integer");
+        let validation = validate(&json!({"additionalProperties": false}), &json!({"x": 1})).unwrap();
+        assert_eq!(validation.at("/x")[0].message, "property \"x\" is not allowed");
+        assert!(validation.is_invalid("/x") && !validation.is_invalid(""));
+    }
+
+    #[test]
+    fn applicators() {
+        let schema = json!({
+            "anyOf": [{"type": "string"}, {"type": "number"}],
+            "oneOf": [{"minimum": 0}, {"maximum": 10}],
+            "not": {"const": 5},
+            "if": {"type": "integer"}, "then": {"minimum": 1}, "else": {"maximum": 0.5}
+        });
+        assert!(validate(&schema, &json!(-1.5)).unwrap().is_valid());
+        assert_eq!(violations(schema.clone(), json!(true)), pairs(&[("", "/anyOf"), ("", "/oneOf")]));
+        assert_eq!(violations(schema.clone(), json!(5)), pairs(&[("", "/oneOf"), ("", "/not")]));
+        assert_eq!(violations(schema, json!(0)), pairs(&[("", "/oneOf"), ("", "/then/minimum")]));
+    }
+
+    #[test]
+    fn arrays() {
+        let schema = json!({
+            "prefixItems": [{"type": "string"}],
+            "items": {"type": "integer"},
+            "contains": {"const": 2},
+            "maxContains": 1
+        });
+        assert!(validate(&schema, &json!(["a", 1, 2])).unwrap().is_valid());
+        assert_eq!(violations(schema.clone(), json!([1, "b"])), pairs(&[
+            ("/0", "/prefixItems/0/type"),
+            ("/1", "/items/type"),
+            ("", "/contains"),
+        ]));
+        assert_eq!(violations(schema, json!(["a", 2, 2])), pairs(&[("", "/maxContains")]));
+    }
+
+    #[test]
+    fn unevaluated() {
+        let schema = json!({
+            "properties": {"a": true},
+            "allOf": [{"properties": {"b": true}}],
+            "patternProperties": {"^x": true},
+            "unevaluatedProperties": false
+        });
+        assert_eq!(violations(schema, json!({"a": 1, "b": 2, "x1": 3, "c": 4})), pairs(&[("/c", "/unevaluatedProperties")]));
+        let schema = json!({"prefixItems": [true], "anyOf": [{"contains": {"const": 9}}], "unevaluatedItems": false});
+        assert_eq!(violations(schema, json!([1, 9, 2])), pairs(&[("/2", "/unevaluatedItems")]));
+    }
+
+    #[test]
+    fn references() {
+        let schema = json!({
+            "$defs": {
+                "positive": {"$anchor": "positive", "exclusiveMinimum": 0},
+                "node": {"$id": "urn:node", "properties": {"next": {"$ref": "#/$defs/node"}, "value": {"$ref": "#positive"}}}
+            },
+            "$ref": "urn:node"
+        });
+        assert!(validate(&schema, &json!({"value": 1, "next": {"value": 2}})).unwrap().is_valid());
+        assert_eq!(violations(schema, json!({"next": {"next": {"value": 0}}})), pairs(&[
+            ("/next/next/value", "/$ref/properties/next/$ref/properties/next/$ref/properties/value/$ref/exclusiveMinimum"),
+        ]));
+    }
+
+    #[test]
+    fn broken_schemas() {
+        assert!(validate(&json!({"$ref": "#/missing"}), &json!(1)).is_err());
+        assert!(validate(&json!({"$ref": "#"}), &json!(1)).is_err());
+        assert!(validate(&json!({"pattern": "("}), &json!("a")).is_err());
+        assert!(validate(&json!({"type": 1}), &json!(1)).is_err());
+        assert!(validate(&json!(1), &json!(1)).is_err());
+    }
+}
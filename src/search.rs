@@ -0,0 +1,376 @@
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+use regex::{Regex, RegexBuilder};
+use serde_json::Value;
+use yew::prelude::*;
+use crate::css::*;
+use crate::document::{JsonPath, PathSegment};
+use crate::error::ViewerError;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchQuery {
+    pub pattern: String,
+    pub regex: bool,
+    pub case_sensitive: bool,
+    pub keys: bool,
+    pub values: bool,
+}
+
+impl SearchQuery {
+    pub fn new(pattern: impl Into<String>) -> Self {
+        Self {
+            pattern: pattern.into(),
+            regex: false,
+            case_sensitive: false,
+            keys: true,
+            values: true,
+        }
+    }
+}
+
+pub enum Matcher {
+    Substring { needle: String, case_sensitive: bool },
+    Regex(Regex),
+}
+
+impl Matcher {
+    pub fn new(query: &SearchQuery) -> Result<Self, ViewerError> {
+        if query.regex {
+            RegexBuilder::new(query.pattern.as_str())
+                .case_insensitive(!query.case_sensitive)
+                .build()
+                .map(Matcher::Regex)
+                .map_err(|e| ViewerError::search(e.to_string()))
+        } else if query.case_sensitive {
+            Ok(Matcher::Substring { needle: query.pattern.clone(), case_sensitive: true })
+        } else {
+            Ok(Matcher::Substring { needle: query.pattern.to_lowercase(), case_sensitive: false })
+        }
+    }
+
+    pub fn is_match(&self, text: &str) -> bool {
+        !self.find_ranges(text).is_empty()
+    }
+
+    pub fn find_ranges(&self, text: &str) -> Vec<(usize, usize)> {
+        match self {
+            Matcher::Regex(regex) => regex
+                .find_iter(text)
+                .filter(|m| !m.is_empty())
+                .map(|m| (m.start(), m.end()))
+                .collect(),
+            Matcher::Substring { needle, .. } if needle.is_empty() => vec![],
+            Matcher::Substring { needle, case_sensitive: true } => text
+                .match_indices(needle.as_str())
+                .map(|(start, s)| (start, start + s.len()))
+                .collect(),
+            Matcher::Substring { needle, case_sensitive: false } => {
+                // lowercasing can change byte lengths, so match char by char on the original text
+                let chars = text.char_indices().collect::<Vec<_>>();
+                let needle = needle.chars().collect::<Vec<_>>();
+                let mut result = vec![];
+                let mut index = 0;
+                while index + needle.len() <= chars.len() {
+                    let found = needle.iter().zip(&chars[index..]).all(|(n, (_, c))| c.to_lowercase().eq(n.to_lowercase()));
+                    if found {
+                        let end = chars.get(index + needle.len()).map(|(i, _)| *i).unwrap_or(text.len());
+                        result.push((chars[index].0, end));
+                        index += needle.len();
+                    } else {
+                        index += 1;
+                    }
+                }
+                result
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchMatch {
+    pub path: JsonPath,
+    pub key: bool,
+}
+
+pub fn scalar_text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        Value::Null => Some("null".to_string()),
+        _ => None,
+    }
+}
+
+fn collect(value: &Value, path: &JsonPath, query: &SearchQuery, matcher: &Matcher, result: &mut Vec<SearchMatch>) {
+    match value {
+        Value::Array(arr) => {
+            for (index, item) in arr.iter().enumerate() {
+                collect(item, &path.child(PathSegment::Index(index)), query, matcher, result);
+            }
+        }
+        Value::Object(object) => {
+            for (key, item) in object.iter() {
+                let child = path.child(PathSegment::Key(key.clone()));
+                if query.keys && matcher.is_match(key) {
+                    result.push(SearchMatch { path: child.clone(), key: true });
+                }
+                collect(item, &child, query, matcher, result);
+            }
+        }
+        _ => {
+            if query.values && scalar_text(value).is_some_and(|text| matcher.is_match(text.as_str())) {
+                result.push(SearchMatch { path: path.clone(), key: false });
+            }
+        }
+    }
+}
+
+pub fn search(value: &Value, query: &SearchQuery) -> Result<Vec<SearchMatch>, ViewerError> {
+    let matcher = Matcher::new(query)?;
+    let mut result = vec![];
+    if !query.pattern.is_empty() {
+        collect(value, &JsonPath::root(), query, &matcher, &mut result);
+    }
+    Ok(result)
+}
+
+/// Search matches indexed by JSON Pointer for lookup while rendering.
+pub struct SearchState {
+    pub matcher: Matcher,
+    pub matches: Vec<SearchMatch>,
+    pub current: Option<usize>,
+    hits: HashMap<String, (bool, bool)>,
+    ancestors: HashSet<String>,
+    current_hit: Option<(String, bool)>,
+}
+
+impl SearchState {
+    pub fn new(value: &Value, query: &SearchQuery, current: usize) -> Result<Self, ViewerError> {
+        let matcher = Matcher::new(query)?;
+        let matches = search(value, query)?;
+        let mut hits = HashMap::<String, (bool, bool)>::new();
+        let mut ancestors = HashSet::new();
+        for item in matches.iter() {
+            let hit = hits.entry(item.path.to_string()).or_default();
+            if item.key { hit.0 = true } else { hit.1 = true }
+            let mut parent = item.path.parent();
+            while let Some(path) = parent {
+                if !ancestors.insert(path.to_string()) {
+                    break;
+                }
+                parent = path.parent();
+            }
+        }
+        let current = (!matches.is_empty()).then(|| current % matches.len());
+        let current_hit = current.map(|index| (matches[index].path.to_string(), matches[index].key));
+        Ok(Self { matcher, matches, current, hits, ancestors, current_hit })
+    }
+
+    pub fn current_pointer(&self) -> Option<&str> {
+        self.current_hit.as_ref().map(|(pointer, _)| pointer.as_str())
+    }
+
+    pub fn is_ancestor(&self, pointer: &str) -> bool {
+        self.ancestors.contains(pointer)
+    }
+
+    pub fn ancestors(&self) -> impl Iterator<Item = &String> {
+        self.ancestors.iter()
+    }
+
+    pub fn key_hit(&self, pointer: &str) -> Option<bool> {
+        self.hit(pointer, true)
+    }
+
+    pub fn value_hit(&self, pointer: &str) -> Option<bool> {
+        self.hit(pointer, false)
+    }
+
+    fn hit(&self, pointer: &str, key: bool) -> Option<bool> {
+        let (key_hit, value_hit) = self.hits.get(pointer)?;
+        (if key { *key_hit } else { *value_hit }).then(|| {
+            self.current_hit.as_ref().is_some_and(|(p, k)| p == pointer && *k == key)
+        })
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct SearchContext(pub Option<Rc<SearchState>>);
+
+impl PartialEq for SearchContext {
+    fn eq(&self, other: &Self) -> bool {
+        match (&self.0, &other.0) {
+            (Some(a), Some(b)) => Rc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+}
+
+impl SearchContext {
+    pub fn state(&self) -> Option<&SearchState> {
+        self.0.as_deref()
+    }
+}
+
+/// Splits `text` into runs outside and inside the matches.
+fn runs<'a>(text: &'a str, matcher: &Matcher) -> Vec<(&'a str, bool)> {
+    let mut result = vec![];
+    let mut last = 0;
+    for (start, end) in matcher.find_ranges(text) {
+        result.push((&text[last..start], false));
+        result.push((&text[start..end], true));
+        last = end;
+    }
+    result.push((&text[last..], false));
+    result
+}
+
+/// `text` as a JSON string literal, split into runs outside and inside the matches. Matching runs on
+/// the raw text, as when counting, and the quotes and escapes are added around the matches.
+fn quoted_runs(text: &str, matcher: &Matcher) -> Vec<(String, bool)> {
+    let mut result = runs(text, matcher).into_iter()
+        .map(|(run, marked)| {
+            let quoted = serde_json::to_string(run).unwrap();
+            (quoted[1..quoted.len() - 1].to_string(), marked)
+        })
+        .collect::<Vec<_>>();
+    result.first_mut().unwrap().0.insert(0, '"');
+    result.last_mut().unwrap().0.push('"');
+    result
+}
+
+fn runs_html<T: AsRef<str>>(runs: &[(T, bool)], current: bool) -> Html {
+    let class = if current { JSON_MATCH_CURRENT.as_str() } else { JSON_MATCH.as_str() };
+    runs.iter().map(|(run, marked)| {
+        let run = run.as_ref().to_string();
+        if *marked {
+            html! { <mark class={classes!(class, current.then_some("yew_json_viewer_current_match"))}>{run}</mark> }
+        } else {
+            html! { <>{run}</> }
+        }
+    }).collect::<Html>()
+}
+
+pub fn highlight(text: &str, matcher: &Matcher, current: bool) -> Html {
+    runs_html(&runs(text, matcher), current)
+}
+
+/// Like `highlight`, for a string shown as a quoted, escaped JSON string.
+pub fn highlight_quoted(text: &str, matcher: &Matcher, current: bool) -> Html {
+    runs_html(&quoted_runs(text, matcher), current)
+}
+
+#[derive(PartialEq, Properties)]
+pub struct SearchBarProps {
+    pub query: SearchQuery,
+    pub count: usize,
+    pub current: Option<usize>,
+    #[prop_or_default]
+    pub error: Option<String>,
+    pub on_query: Callback<SearchQuery>,
+    pub on_step: Callback<isize>,
+}
+
+#[function_component(SearchBar)]
+pub fn search_bar(props: &SearchBarProps) -> Html {
+    let SearchBarProps { query, count, current, error, on_query, on_step } = props;
+    let oninput = Callback::from({
+        let query = query.clone();
+        let on_query = on_query.clone();
+        move |e: InputEvent| {
+            let input: web_sys::HtmlInputElement = e.target_unchecked_into();
+            on_query.emit(SearchQuery { pattern: input.value(), ..query.clone() });
+        }
+    });
+    let onkeydown = Callback::from({
+        let on_step = on_step.clone();
+        move |e: KeyboardEvent| {
+            if e.key() == "Enter" {
+                on_step.emit(if e.shift_key() { -1 } else { 1 });
+            }
+        }
+    });
+    let toggle = |label: &'static str, title: &'static str, checked: bool, update: fn(&mut SearchQuery)| {
+        let onclick = Callback::from({
+            let query = query.clone();
+            let on_query = on_query.clone();
+            move |_: MouseEvent| {
+                let mut query = query.clone();
+                update(&mut query);
+                on_query.emit(query);
+            }
+        });
+        html! {
+            <span style="margin-left: 0.6em; cursor: pointer; user-select: none;" {title} {onclick}>
+                <input type="checkbox" {checked} style="cursor: pointer;"/>
+                <label style="margin-left: 0.2em; cursor: pointer;">{label}</label>
+            </span>
+        }
+    };
+    let counter = match (error, current) {
+        (Some(error), _) => error.clone(),
+        (None, Some(current)) => format!("{} / {}", current + 1, count),
+        (None, None) if query.pattern.is_empty() => "".to_string(),
+        (None, None) => "0 / 0".to_string(),
+    };
+    let step = |delta: isize, label: &'static str| {
+        let onclick = on_step.reform(move |_: MouseEvent| delta);
+        html! {
+            <button type="button" class={classes!(JSON_TOOLBAR_BUTTON.as_str())} disabled={*count == 0} {onclick}>{label}</button>
+        }
+    };
+    html! {
+        <div class={classes!(JSON_TOOLBAR.as_str())}>
+            <input type="search" placeholder="Search keys and values" value={query.pattern.clone()} {oninput} {onkeydown}/>
+            {step(-1, "\u{2191}")}
+            {step(1, "\u{2193}")}
+            <span style="margin-left: 0.6em; color: #888;">{counter}</span>
+            {toggle("Keys", "Match object keys", query.keys, |q| q.keys = !q.keys)}
+            {toggle("Values", "Match values", query.values, |q| q.values = !q.values)}
+            {toggle("Aa", "Case sensitive", query.case_sensitive, |q| q.case_sensitive = !q.case_sensitive)}
+            {toggle(".*", "Regular expression", query.regex, |q| q.regex = !q.regex)}
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matcher(pattern: &str, regex: bool) -> Matcher {
+        Matcher::new(&SearchQuery { regex, ..SearchQuery::new(pattern) }).unwrap()
+    }
+
+    fn marked(runs: Vec<(String, bool)>) -> Vec<String> {
+        runs.into_iter().filter(|(_, marked)| *marked).map(|(run, _)| run).collect()
+    }
+
+    #[test]
+    fn anchored_patterns_match_the_raw_string() {
+        let runs = quoted_runs("foo bar foo", &matcher("^foo", true));
+        assert_eq!(runs.iter().map(|(run, _)| run.as_str()).collect::<String>(), r#""foo bar foo""#);
+        assert_eq!(marked(runs), vec!["foo"]);
+        assert_eq!(marked(quoted_runs("foo bar foo", &matcher("foo$", true))), vec!["foo"]);
+        assert_eq!(marked(quoted_runs("foo", &matcher("^foo$", true))), vec!["foo"]);
+    }
+
+    #[test]
+    fn matches_containing_escaped_characters_are_marked() {
+        let text = "say \"hi\"\n\\";
+        assert!(matcher("\"hi\"", false).is_match(text));
+        let runs = quoted_runs(text, &matcher("\"hi\"", false));
+        assert_eq!(runs.iter().map(|(run, _)| run.as_str()).collect::<String>(), serde_json::to_string(text).unwrap());
+        assert_eq!(marked(runs), vec![r#"\"hi\""#]);
+        assert_eq!(marked(quoted_runs(text, &matcher("\n\\", false))), vec![r#"\n\\"#]);
+    }
+
+    #[test]
+    fn counts_keys_and_values() {
+        let value = serde_json::json!({"name": "x", "list": ["name", 1]});
+        let matches = search(&value, &SearchQuery::new("NAME")).unwrap();
+        let paths = matches.iter().map(|item| (item.path.to_string(), item.key)).collect::<Vec<_>>();
+        assert_eq!(paths, vec![("/name".to_string(), true), ("/list/0".to_string(), false)]);
+    }
+}
@@ -0,0 +1,37 @@
+use yew::prelude::*;
+use crate::css::*;
+
+#[derive(PartialEq, Properties)]
+pub struct ToolbarProps {
+    /// Emits the depth to expand to; `usize::MAX` expands everything and `0` collapses the root.
+    pub on_expand: Callback<usize>,
+}
+
+#[function_component(Toolbar)]
+pub fn toolbar(props: &ToolbarProps) -> Html {
+    let ToolbarProps { on_expand } = props;
+    let depth = use_state(|| 1usize);
+    let oninput = Callback::from({
+        let depth = depth.clone();
+        move |e: InputEvent| {
+            let input: web_sys::HtmlInputElement = e.target_unchecked_into();
+            if let Ok(value) = input.value().parse::<usize>() {
+                depth.set(value);
+            }
+        }
+    });
+    let button = |label: &'static str, target: usize| {
+        let onclick = on_expand.reform(move |_: MouseEvent| target);
+        html! {
+            <button type="button" class={classes!(JSON_TOOLBAR_BUTTON.as_str())} {onclick}>{label}</button>
+        }
+    };
+    html! {
+        <div class={classes!(JSON_TOOLBAR.as_str())}>
+            {button("Expand all", usize::MAX)}
+            {button("Collapse all", 0)}
+            <input type="number" min="0" value={depth.to_string()} {oninput}/>
+            {button("Expand to depth", *depth)}
+        </div>
+    }
+}
@@ -0,0 +1,345 @@
+use serde_json::Value;
+use web_sys::HtmlElement;
+use yew::prelude::*;
+use crate::collapse::{toggle_callback, CollapseContext, CollapseStore};
+use crate::common::{is_collapsable, value_length};
+use crate::core::{annotations_html, change_class, change_title, collapsed_class, duplicate_badge, is_string_json, ExpandState, line_number_html, placeholder_text, quoted_html, repairs_html, ScrollTarget, value_html, violation_class, violations_html, RenderProps};
+use crate::css::*;
+use crate::document::{PathSegment, ValueRef};
+use crate::interaction::ButtonControlDialogJsonViewer;
+use crate::jsonpath::{JsonPathContext, JsonPathState};
+use crate::schema::SchemaContext;
+use crate::search::{SearchContext, SearchState};
+use crate::JsonViewerOption;
+
+const OVERSCAN: usize = 20;
+
+#[derive(Clone, Copy, PartialEq)]
+enum RowKind {
+    Value,
+    Open,
+    Close,
+}
+
+struct Row {
+    value: ValueRef,
+    depth: usize,
+    kind: RowKind,
+    collapsed: bool,
+    last: bool,
+}
+
+impl Row {
+    fn key(&self) -> Option<&str> {
+        match self.value.path().last() {
+            Some(PathSegment::Key(key)) if self.depth > 0 => Some(key.as_str()),
+            _ => None,
+        }
+    }
+}
+
+struct Flattener<'b> {
+    rows: Vec<Row>,
+    depth: usize,
+    option: &'b JsonViewerOption,
+    expand: ExpandState,
+    collapse: &'b CollapseStore,
+    search: Option<&'b SearchState>,
+    target: Option<&'b str>,
+    pointer: String,
+    current_row: Option<usize>,
+    target_row: Option<usize>,
+}
+
+impl Flattener<'_> {
+    fn push_child(&mut self, value: &Value, value_ref: &ValueRef, segment: PathSegment, last: bool) {
+        self.depth += 1;
+        let length = self.pointer.len();
+        self.pointer.push_str(format!("/{}", segment).as_str());
+        self.push(value, value_ref.child(segment), last);
+        self.pointer.truncate(length);
+        self.depth -= 1;
+    }
+
+    fn push(&mut self, value: &Value, value_ref: ValueRef, last: bool) {
+        let depth = self.depth;
+        let mut row = Row { value: value_ref, depth, kind: RowKind::Value, collapsed: false, last };
+        if self.search.and_then(|search| search.current_pointer()).is_some_and(|pointer| pointer == self.pointer) {
+            self.current_row = Some(self.rows.len());
+        }
+        if self.target == Some(self.pointer.as_str()) {
+            self.target_row = Some(self.rows.len());
+        }
+        if !is_collapsable(value) {
+            self.rows.push(row);
+            return;
+        }
+        row.collapsed = self.collapse.get(self.pointer.as_str())
+            .unwrap_or_else(|| self.option.is_default_collapsed(value, depth, &self.expand));
+        if row.collapsed {
+            self.rows.push(row);
+            return;
+        }
+        let value_ref = row.value.clone();
+        self.rows.push(Row { kind: RowKind::Open, ..row });
+        let length = value_length(value);
+        match value {
+            Value::Array(arr) => {
+                for (index, item) in arr.iter().enumerate() {
+                    self.push_child(item, &value_ref, PathSegment::Index(index), index + 1 == length);
+                }
+            }
+            Value::Object(object) => {
+                for (index, (key, item)) in object.iter().enumerate() {
+                    self.push_child(item, &value_ref, PathSegment::Key(key.clone()), index + 1 == length);
+                }
+            }
+            _ => {}
+        }
+        self.rows.push(Row { value: value_ref, depth, kind: RowKind::Close, collapsed: false, last });
+    }
+}
+
+struct Flattened {
+    rows: Vec<Row>,
+    /// Row of the current search match.
+    current_row: Option<usize>,
+    /// Row of the `ScrollTarget`.
+    target_row: Option<usize>,
+}
+
+/// The rows of every expanded node in document order.
+fn flatten(
+    value: &ValueRef,
+    option: &JsonViewerOption,
+    expand: ExpandState,
+    collapse: &CollapseStore,
+    search: Option<&SearchState>,
+    target: Option<&str>,
+) -> Flattened {
+    let mut flattener = Flattener {
+        rows: vec![],
+        depth: 0,
+        option,
+        expand,
+        collapse,
+        search,
+        target,
+        pointer: String::new(),
+        current_row: None,
+        target_row: None,
+    };
+    flattener.push(value.get(), value.clone(), true);
+    Flattened { rows: flattener.rows, current_row: flattener.current_row, target_row: flattener.target_row }
+}
+
+fn row_html(
+    row: &Row,
+    option: &JsonViewerOption,
+    search: Option<&SearchState>,
+    jsonpath: Option<&JsonPathState>,
+    schema: &SchemaContext,
+    onclick: Option<Callback<MouseEvent>>,
+) -> Html {
+    let value_ref = &row.value;
+    let value = value_ref.get();
+    let is_root = row.depth == 0;
+    let pointer = search.map(|_| value_ref.path().to_string());
+    let dimmed = jsonpath.is_some_and(|state| state.is_dimmed(value_ref.path()));
+    let highlight = |key: bool| {
+        let search = search?;
+        let pointer = pointer.as_ref()?;
+        let current = if key { search.key_hit(pointer) } else { search.value_hit(pointer) }?;
+        Some((&search.matcher, current))
+    };
+    let shadowed = row.key()
+        .filter(|_| !value_ref.duplicates().is_empty())
+        .and_then(|_| value_ref.duplicates().get(value_ref.path().to_string().as_str()));
+    let key_repr = row.key().map(|key| html! {
+        <>
+            <span class={classes!(JSON_STRING.as_str())}>
+                {quoted_html(key, highlight(true))}
+            </span>
+            {shadowed.map(duplicate_badge)}
+        </>
+    });
+    let toggle = match (&onclick, key_repr) {
+        (Some(onclick), Some(key_repr)) => html! {
+            <>
+                <a href="" class={classes!(JSON_TOGGLE.as_str(), collapsed_class(row.collapsed))} onclick={onclick.clone()}>{key_repr}</a>
+                {": "}
+            </>
+        },
+        (Some(onclick), None) => html! {
+            <a href="" class={classes!(JSON_TOGGLE.as_str(), collapsed_class(row.collapsed))} onclick={onclick.clone()}/>
+        },
+        (None, Some(key_repr)) => html! { <>{key_repr}{": "}</> },
+        (None, None) => html! {},
+    };
+    let mut interactions = vec![];
+    if row.kind != RowKind::Close {
+        if is_root {
+            if let Some(value) = is_string_json(value, *option.use_json5.borrow()) {
+                interactions.push(html! { <ButtonControlDialogJsonViewer value={ValueRef::new(value)} /> });
+            }
+        } else {
+            interactions.append(&mut (option.value_to_element)(value_ref));
+        }
+        if let Some(additional_value_to_element) = &option.additional_value_to_element {
+            interactions.append(&mut (additional_value_to_element)(value_ref));
+        }
+    }
+    let (open, close) = if value.is_array() { ("[", "]") } else { ("{", "}") };
+    let element = match row.kind {
+        RowKind::Value if row.collapsed => html! {
+            <>
+                {open}
+                <a href="" class={classes!(JSON_PLACEHOLDER.as_str())} {onclick}>{placeholder_text(value_length(value))}</a>
+                {close}
+            </>
+        },
+        RowKind::Value => value_html(value_ref, value, highlight(false)),
+        RowKind::Open => html! { <>{open}</> },
+        RowKind::Close => html! { <>{close}</> },
+    };
+    let style = format!(
+        "height: {height}px; line-height: {height}px; padding-left: {}em;",
+        row.depth * 2,
+        height = option.virtual_row_height,
+    );
+    html! {
+        <div class={classes!(
+                JSON_VIRTUAL_ROW.as_str(),
+                dimmed.then(|| JSON_DIMMED.as_str()),
+                change_class(value_ref),
+                violation_class(value_ref, schema),
+             )}
+             title={change_title(value_ref)} {style}>
+            if row.kind != RowKind::Close {
+                {line_number_html(value_ref)}
+                {toggle}
+            }
+            {interactions}
+            if row.kind != RowKind::Close {
+                {annotations_html(value_ref)}
+            }
+            {element}
+            if row.kind != RowKind::Close {
+                {repairs_html(value_ref)}
+                {violations_html(value_ref, schema)}
+            }
+            if !row.last && row.kind != RowKind::Open {
+                {","}
+            }
+        </div>
+    }
+}
+
+fn center_row(element: &HtmlElement, row: usize, row_height: f64) {
+    let top = row as f64 * row_height - element.client_height() as f64 / 2.0;
+    element.set_scroll_top(top.max(0.0) as i32);
+}
+
+#[function_component(VirtualRender)]
+pub fn virtual_render(props: &RenderProps) -> Html {
+    let RenderProps { value, option, .. } = props;
+    let container = use_node_ref();
+    let scroll_top = use_state(|| 0.0);
+    let viewport_height = use_state(|| 0.0);
+    let collapse = use_context::<CollapseContext>().expect("VirtualRender is mounted inside RootRender");
+    let expand = use_context::<ExpandState>().unwrap_or_default();
+    let search = use_context::<SearchContext>().unwrap_or_default();
+    let jsonpath = use_context::<JsonPathContext>().unwrap_or_default();
+    let schema = use_context::<SchemaContext>().unwrap_or_default();
+    let scroll_target = use_context::<ScrollTarget>().unwrap_or_default();
+    let pending_scroll = use_mut_ref(|| false);
+    use_effect_with((expand, search.clone(), jsonpath.clone()), {
+        let pending_scroll = pending_scroll.clone();
+        move |_| {
+            *pending_scroll.borrow_mut() = true;
+            || {}
+        }
+    });
+    use_effect_with((), {
+        let container = container.clone();
+        let viewport_height = viewport_height.clone();
+        move |_| {
+            if let Some(element) = container.cast::<HtmlElement>() {
+                viewport_height.set(element.client_height() as f64);
+            }
+            || {}
+        }
+    });
+    let onscroll = Callback::from({
+        let container = container.clone();
+        let scroll_top = scroll_top.clone();
+        let viewport_height = viewport_height.clone();
+        move |_: Event| {
+            if let Some(element) = container.cast::<HtmlElement>() {
+                scroll_top.set(element.scroll_top() as f64);
+                viewport_height.set(element.client_height() as f64);
+            }
+        }
+    });
+    // scrolling only re-slices the rows
+    let flattened = use_memo(
+        (value.clone(), option.clone(), expand, collapse.clone(), search.clone(), scroll_target.pointer.clone()),
+        |(value, option, expand, collapse, search, target)| flatten(value, option, *expand, collapse, search.state(), target.as_deref()),
+    );
+    let Flattened { rows, current_row, target_row } = &*flattened;
+    let current_row = *current_row;
+    let row_height = option.virtual_row_height;
+    use_effect_with((search.clone(), current_row), {
+        let container = container.clone();
+        move |(_, current_row): &(SearchContext, Option<usize>)| {
+            if current_row.is_some() && *pending_scroll.borrow() {
+                *pending_scroll.borrow_mut() = false;
+                if let (Some(element), Some(current_row)) = (container.cast::<HtmlElement>(), current_row) {
+                    center_row(&element, *current_row, row_height);
+                }
+            }
+            || {}
+        }
+    });
+    // jumps to the scroll target that were already scrolled to
+    let scrolled_jumps = use_mut_ref(|| 0);
+    use_effect_with((scroll_target.jumps, *target_row), {
+        let container = container.clone();
+        move |(jumps, target_row): &(usize, Option<usize>)| {
+            if let (Some(element), Some(target_row)) = (container.cast::<HtmlElement>(), target_row) {
+                if *scrolled_jumps.borrow() != *jumps {
+                    *scrolled_jumps.borrow_mut() = *jumps;
+                    center_row(&element, *target_row, row_height);
+                }
+            }
+            || {}
+        }
+    });
+    let start = ((*scroll_top / row_height) as usize).saturating_sub(OVERSCAN).min(rows.len());
+    let end = (((*scroll_top + *viewport_height) / row_height).ceil() as usize + OVERSCAN).min(rows.len());
+    let visible = (start..end)
+        .map(|index| {
+            let row = &rows[index];
+            let onclick = (is_collapsable(row.value.get()) && row.kind != RowKind::Close)
+                .then(|| toggle_callback(collapse.clone(), row.value.path().to_string(), row.collapsed));
+            html! {
+                <div key={index}>
+                    {row_html(row, option, search.state(), jsonpath.state(), &schema, onclick)}
+                </div>
+            }
+        })
+        .collect::<Vec<_>>();
+    let before = format!("height: {}px;", start as f64 * row_height);
+    let after = format!("height: {}px;", (rows.len() - end) as f64 * row_height);
+    let style = format!(
+        "height: {};",
+        if option.dialog_index.is_some() { "77vh" } else { option.virtual_viewport_height.as_str() },
+    );
+    html! {
+        <pre class={classes!(JSON_DOCUMENT.as_str())} {style} ref={container} {onscroll}>
+            <div style={before}/>
+            {visible}
+            <div style={after}/>
+        </pre>
+    }
+}